@@ -0,0 +1,23 @@
+use std::error::Error;
+use tera::Tera;
+use trusty_tail::config::Config;
+use trusty_tail::connection;
+use trusty_tail::jobs::check_health::run;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    pretty_env_logger::init();
+    log::info!("Starting...");
+
+    let config = Config::init();
+    let connection = connection::init().await?;
+
+    let tera = match Tera::new("templates/**/*") {
+        Ok(tera) => tera,
+        Err(message) => panic!("Tera error: {}", message),
+    };
+
+    run(&connection, &config, &tera).await.unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use std::error::Error;
+use trusty_tail::config::Config;
+use trusty_tail::connection;
+use trusty_tail::emergency_info::utils::rotate_keys;
+
+// Run after adding a new key to ENCRYPTION_KEYS and making it ENCRYPTION_KEY_ID,
+// the old key can be removed once nothing is left to rotate
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    pretty_env_logger::init();
+    log::info!("Starting...");
+
+    let config = Config::init();
+    let connection = connection::init().await?;
+
+    let rotated = rotate_keys(&connection, &config.keyring).await.unwrap();
+    log::info!(
+        "Rotated {} emergency info sections, captions and pet care notes to key {}",
+        rotated,
+        config.keyring.active_key_id()
+    );
+
+    Ok(())
+}
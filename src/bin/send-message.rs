@@ -1,12 +1,14 @@
-use sea_orm::{Database, EntityTrait, PaginatorTrait};
+use sea_orm::{ColumnTrait, Database, EntityTrait, PaginatorTrait, QueryFilter};
 use std::{
     error::Error,
     io::{self, Read},
 };
-use teloxide::{requests::Requester, types::ChatId, Bot};
+use teloxide::types::ChatId;
 use trusty_tail::config::Config;
 use trusty_tail::entity::profiles;
+use trusty_tail::outbox::utils::enqueue_message;
 
+// Only queues the messages, the bot's dispatcher sends them within Telegram's limits
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     pretty_env_logger::init();
@@ -15,7 +17,6 @@ async fn main() -> Result<(), Box<dyn Error>> {
     log::info!("Initialized config...");
     let connection = Database::connect(config.db_url).await?;
     log::info!("Connected to database...");
-    let bot = Bot::from_env();
 
     let mut input = String::new();
     io::stdin()
@@ -29,11 +30,18 @@ async fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    let mut profile_pages = profiles::Entity::find().paginate(&connection, 50);
+    let mut profile_pages = profiles::Entity::find()
+        .filter(profiles::Column::UnreachableAt.is_null())
+        .paginate(&connection, 50);
 
     while let Some(profiles) = profile_pages.fetch_and_next().await? {
         for profile in profiles {
-            let _ = bot.send_message(ChatId(profile.chat_id), input).await;
+            let chat_id = ChatId(profile.chat_id);
+            if let Err(error) =
+                enqueue_message(&connection, chat_id, input.to_string(), None, None).await
+            {
+                log::error!("Can't queue the message for {:?}: {}", profile, error);
+            }
         }
     }
 
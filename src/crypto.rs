@@ -0,0 +1,144 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+const NONCE_SIZE: usize = 12;
+
+// Master keys by id, new data keys are always wrapped with the active one
+#[derive(Clone)]
+pub struct Keyring {
+    active_key_id: String,
+    keys: HashMap<String, Key<Aes256Gcm>>,
+}
+
+// Never print the keys themselves
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sealed {
+    pub ciphertext: Vec<u8>,
+    pub nonce: Vec<u8>,
+    // The per-row data key, encrypted with the master key, prefixed with its own nonce
+    pub data_key: Vec<u8>,
+    pub key_id: String,
+}
+
+impl Keyring {
+    // `keys` is a comma-separated list of `<id>:<base64 of 32 bytes>`
+    pub fn parse(active_key_id: &str, keys: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut keyring = Keyring {
+            active_key_id: active_key_id.to_string(),
+            keys: HashMap::new(),
+        };
+
+        for entry in keys.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let (id, key) = match entry.split_once(':') {
+                Some((id, key)) => (id.trim(), key.trim()),
+                None => return Err("Key entries must look like <id>:<base64 key>".into()),
+            };
+            let key = STANDARD.decode(key)?;
+            if key.len() != 32 {
+                return Err(format!("Key {} must be 32 bytes long", id).into());
+            }
+            keyring
+                .keys
+                .insert(id.to_string(), *Key::<Aes256Gcm>::from_slice(&key));
+        }
+
+        if !keyring.keys.contains_key(active_key_id) {
+            return Err(format!("Active key {} is not in the keyring", active_key_id).into());
+        }
+        Ok(keyring)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    fn get_cipher(&self, key_id: &str) -> Result<Aes256Gcm, Box<dyn Error + Send + Sync>> {
+        match self.keys.get(key_id) {
+            Some(key) => Ok(Aes256Gcm::new(key)),
+            None => Err(format!("Unknown key id: {}", key_id).into()),
+        }
+    }
+
+    fn wrap_data_key(
+        &self,
+        data_key: &Key<Aes256Gcm>,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let wrapped = self
+            .get_cipher(&self.active_key_id)?
+            .encrypt(&nonce, data_key.as_slice())
+            .map_err(|_| "Can't wrap data key")?;
+        Ok([nonce.as_slice(), &wrapped].concat())
+    }
+
+    fn unwrap_data_key(
+        &self,
+        data_key: &[u8],
+        key_id: &str,
+    ) -> Result<Key<Aes256Gcm>, Box<dyn Error + Send + Sync>> {
+        if data_key.len() <= NONCE_SIZE {
+            return Err("Malformed data key".into());
+        }
+        let (nonce, wrapped) = data_key.split_at(NONCE_SIZE);
+        let data_key = self
+            .get_cipher(key_id)?
+            .decrypt(Nonce::from_slice(nonce), wrapped)
+            .map_err(|_| "Can't unwrap data key")?;
+        if data_key.len() != 32 {
+            return Err("Malformed data key".into());
+        }
+        Ok(*Key::<Aes256Gcm>::from_slice(&data_key))
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<Sealed, Box<dyn Error + Send + Sync>> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(&data_key)
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| "Can't encrypt")?;
+
+        Ok(Sealed {
+            ciphertext,
+            nonce: nonce.to_vec(),
+            data_key: self.wrap_data_key(&data_key)?,
+            key_id: self.active_key_id.clone(),
+        })
+    }
+
+    pub fn open(&self, sealed: &Sealed) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if sealed.nonce.len() != NONCE_SIZE {
+            return Err("Malformed nonce".into());
+        }
+        let data_key = self.unwrap_data_key(&sealed.data_key, &sealed.key_id)?;
+        let plaintext = Aes256Gcm::new(&data_key)
+            .decrypt(
+                Nonce::from_slice(&sealed.nonce),
+                sealed.ciphertext.as_slice(),
+            )
+            .map_err(|_| "Can't decrypt")?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    // Re-encrypts only the data key with the active master key, the ciphertext stays as is
+    pub fn rewrap(&self, sealed: &Sealed) -> Result<Sealed, Box<dyn Error + Send + Sync>> {
+        let data_key = self.unwrap_data_key(&sealed.data_key, &sealed.key_id)?;
+        Ok(Sealed {
+            data_key: self.wrap_data_key(&data_key)?,
+            key_id: self.active_key_id.clone(),
+            ..sealed.clone()
+        })
+    }
+}
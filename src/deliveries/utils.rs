@@ -0,0 +1,147 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::prelude::*;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveValue, JoinType, QueryOrder, QuerySelect};
+use std::error::Error;
+
+use crate::entity::{
+    alert_deliveries::{self, DeliveryStatus},
+    incidents, secondary_owners,
+};
+use crate::notifiers::NotifyError;
+
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+pub const DELIVERY_BACKOFF_MINUTES: i64 = 5;
+
+// Doubles with every attempt: 5, 10, 20, 40 minutes
+pub fn get_backoff(attempts: i32) -> Duration {
+    Duration::minutes(DELIVERY_BACKOFF_MINUTES << (attempts - 1).clamp(0, 10))
+}
+
+// Safe to repeat, contacts that already have a delivery for the incident are skipped
+pub async fn create_deliveries<C: ConnectionTrait>(
+    connection: &C,
+    incident_id: i32,
+    links: &[secondary_owners::Model],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if links.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now().naive_utc();
+    alert_deliveries::Entity::insert_many(links.iter().map(|link| alert_deliveries::ActiveModel {
+        incident_id: ActiveValue::Set(incident_id),
+        link_id: ActiveValue::Set(link.id),
+        status: ActiveValue::Set(DeliveryStatus::Pending),
+        next_attempt_at: ActiveValue::Set(now),
+        created_at: ActiveValue::Set(now),
+        ..Default::default()
+    }))
+    .on_conflict(
+        OnConflict::columns([
+            alert_deliveries::Column::IncidentId,
+            alert_deliveries::Column::LinkId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
+
+pub fn select_due_deliveries(
+    incident_id: i32,
+    now: NaiveDateTime,
+) -> Select<alert_deliveries::Entity> {
+    alert_deliveries::Entity::find()
+        .filter(alert_deliveries::Column::IncidentId.eq(incident_id))
+        .filter(alert_deliveries::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(alert_deliveries::Column::NextAttemptAt.lte(now))
+        .order_by_asc(alert_deliveries::Column::Id)
+}
+
+// Open incidents with alerts waiting for another try
+pub fn select_incidents_to_retry(now: NaiveDateTime) -> Select<incidents::Entity> {
+    incidents::Entity::find()
+        .join_rev(
+            JoinType::InnerJoin,
+            alert_deliveries::Entity::belongs_to(incidents::Entity)
+                .from(alert_deliveries::Column::IncidentId)
+                .to(incidents::Column::Id)
+                .into(),
+        )
+        .filter(incidents::Column::ResolvedAt.is_null())
+        .filter(alert_deliveries::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(alert_deliveries::Column::NextAttemptAt.lte(now))
+        .group_by(incidents::Column::Id)
+        .order_by_asc(incidents::Column::Id)
+}
+
+// Counts the attempt and pushes the next one back before sending, so a crash
+// or a concurrent run can't send the same delivery twice in a row. Returns
+// `false` if somebody else already took this attempt.
+pub async fn claim_delivery(
+    connection: &DatabaseConnection,
+    delivery: &alert_deliveries::Model,
+    now: NaiveDateTime,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let attempts = delivery.attempts + 1;
+    let result = alert_deliveries::Entity::update_many()
+        .set(alert_deliveries::ActiveModel {
+            attempts: ActiveValue::Set(attempts),
+            next_attempt_at: ActiveValue::Set(now + get_backoff(attempts)),
+            ..Default::default()
+        })
+        .filter(alert_deliveries::Column::Id.eq(delivery.id))
+        .filter(alert_deliveries::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(alert_deliveries::Column::Attempts.eq(delivery.attempts))
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+pub async fn mark_delivery_sent(
+    connection: &DatabaseConnection,
+    delivery_id: i32,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    alert_deliveries::Entity::update_many()
+        .set(alert_deliveries::ActiveModel {
+            status: ActiveValue::Set(DeliveryStatus::Sent),
+            sent_at: ActiveValue::Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        })
+        .filter(alert_deliveries::Column::Id.eq(delivery_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Expects a claimed delivery, returns whether it's still worth retrying
+pub async fn record_delivery_error(
+    connection: &DatabaseConnection,
+    delivery: &alert_deliveries::Model,
+    error: &NotifyError,
+) -> Result<DeliveryStatus, Box<dyn Error + Send + Sync>> {
+    let status = match error {
+        NotifyError::Transient(_) if delivery.attempts + 1 < MAX_DELIVERY_ATTEMPTS => {
+            DeliveryStatus::Pending
+        }
+        _ => DeliveryStatus::Failed,
+    };
+
+    alert_deliveries::Entity::update_many()
+        .set(alert_deliveries::ActiveModel {
+            status: ActiveValue::Set(status),
+            last_error: ActiveValue::Set(Some(error.to_string())),
+            ..Default::default()
+        })
+        .filter(alert_deliveries::Column::Id.eq(delivery.id))
+        .exec(connection)
+        .await?;
+
+    Ok(status)
+}
@@ -0,0 +1,301 @@
+use chrono::Utc;
+use sea_orm::prelude::*;
+use sea_orm::{sea_query::OnConflict, ActiveValue, QueryOrder};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::{
+    crypto::{Keyring, Sealed},
+    entity::{
+        emergency_attachments::{self, AttachmentKind},
+        emergency_info::{self, EmergencySection},
+    },
+    pets::utils::rotate_care_keys,
+};
+
+// The order the wizard asks in and the alert shows the sections
+pub const EMERGENCY_SECTIONS: [EmergencySection; 6] = [
+    EmergencySection::HomeAccess,
+    EmergencySection::Documents,
+    EmergencySection::Health,
+    EmergencySection::Diet,
+    EmergencySection::Instructions,
+    EmergencySection::Vet,
+];
+
+pub fn parse_section(code: i32) -> Option<EmergencySection> {
+    EmergencySection::try_from_value(&code).ok()
+}
+
+pub fn get_section_index(section: EmergencySection) -> usize {
+    EMERGENCY_SECTIONS
+        .iter()
+        .position(|x| *x == section)
+        .unwrap_or_default()
+}
+
+pub fn get_next_section(section: EmergencySection) -> Option<EmergencySection> {
+    EMERGENCY_SECTIONS
+        .get(get_section_index(section) + 1)
+        .copied()
+}
+
+pub fn get_previous_section(section: EmergencySection) -> Option<EmergencySection> {
+    get_section_index(section)
+        .checked_sub(1)
+        .map(|index| EMERGENCY_SECTIONS[index])
+}
+
+// Decrypted section texts, a section that can't be decrypted is skipped so alerts still go out
+pub async fn get_sections(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+) -> Result<Vec<(EmergencySection, String)>, Box<dyn Error + Send + Sync>> {
+    let mut rows = emergency_info::Entity::find()
+        .filter(emergency_info::Column::ChatId.eq(chat_id.0))
+        .all(connection)
+        .await?;
+    rows.sort_by_key(|x| get_section_index(x.section));
+
+    let mut sections = vec![];
+    for row in rows {
+        let sealed = Sealed {
+            ciphertext: row.ciphertext,
+            nonce: row.nonce,
+            data_key: row.data_key,
+            key_id: row.key_id,
+        };
+        match keyring.open(&sealed) {
+            Ok(text) => sections.push((row.section, text)),
+            Err(err) => log::error!("Can't decrypt emergency info {}: {}", row.id, err),
+        }
+    }
+
+    Ok(sections)
+}
+
+// `None` removes the section
+pub async fn set_section(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+    section: EmergencySection,
+    text: Option<String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let text = match text {
+        Some(text) => text,
+        None => {
+            emergency_info::Entity::delete_many()
+                .filter(emergency_info::Column::ChatId.eq(chat_id.0))
+                .filter(emergency_info::Column::Section.eq(section))
+                .exec(connection)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let sealed = keyring.seal(&text)?;
+    emergency_info::Entity::insert(emergency_info::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        section: ActiveValue::Set(section),
+        ciphertext: ActiveValue::Set(sealed.ciphertext),
+        nonce: ActiveValue::Set(sealed.nonce),
+        data_key: ActiveValue::Set(sealed.data_key),
+        key_id: ActiveValue::Set(sealed.key_id),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([
+            emergency_info::Column::ChatId,
+            emergency_info::Column::Section,
+        ])
+        .update_columns([
+            emergency_info::Column::Ciphertext,
+            emergency_info::Column::Nonce,
+            emergency_info::Column::DataKey,
+            emergency_info::Column::KeyId,
+        ])
+        .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+// An attachment with its caption decrypted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub id: i32,
+    pub kind: AttachmentKind,
+    pub file_id: Option<String>,
+    pub caption: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+fn get_sealed_caption(row: &emergency_attachments::Model) -> Option<Sealed> {
+    Some(Sealed {
+        ciphertext: row.caption_ciphertext.clone()?,
+        nonce: row.caption_nonce.clone()?,
+        data_key: row.caption_data_key.clone()?,
+        key_id: row.caption_key_id.clone()?,
+    })
+}
+
+pub fn select_attachments(chat_id: ChatId) -> Select<emergency_attachments::Entity> {
+    emergency_attachments::Entity::find()
+        .filter(emergency_attachments::Column::ChatId.eq(chat_id.0))
+        .order_by_asc(emergency_attachments::Column::Id)
+}
+
+// A caption that can't be decrypted is dropped, the attachment itself still goes out
+pub async fn get_attachments(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+) -> Result<Vec<Attachment>, Box<dyn Error + Send + Sync>> {
+    let rows = select_attachments(chat_id).all(connection).await?;
+
+    let mut attachments = vec![];
+    for row in rows {
+        let caption = match get_sealed_caption(&row).map(|sealed| keyring.open(&sealed)) {
+            Some(Ok(caption)) => Some(caption),
+            Some(Err(err)) => {
+                log::error!("Can't decrypt attachment caption {}: {}", row.id, err);
+                None
+            }
+            None => None,
+        };
+        attachments.push(Attachment {
+            id: row.id,
+            kind: row.kind,
+            file_id: row.file_id,
+            caption,
+            latitude: row.latitude,
+            longitude: row.longitude,
+        });
+    }
+
+    Ok(attachments)
+}
+
+// Returns `None` if the message has nothing to attach
+pub async fn add_attachment(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    message: &Message,
+) -> Result<Option<emergency_attachments::Model>, Box<dyn Error + Send + Sync>> {
+    let mut attachment = emergency_attachments::ActiveModel {
+        chat_id: ActiveValue::Set(message.chat.id.0),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    if let Some(caption) = message.caption() {
+        let sealed = keyring.seal(caption)?;
+        attachment.caption_ciphertext = ActiveValue::Set(Some(sealed.ciphertext));
+        attachment.caption_nonce = ActiveValue::Set(Some(sealed.nonce));
+        attachment.caption_data_key = ActiveValue::Set(Some(sealed.data_key));
+        attachment.caption_key_id = ActiveValue::Set(Some(sealed.key_id));
+    }
+
+    if let Some(photo) = message.photo().and_then(|sizes| sizes.last()) {
+        attachment.kind = ActiveValue::Set(AttachmentKind::Photo);
+        attachment.file_id = ActiveValue::Set(Some(photo.file.id.clone()));
+    } else if let Some(document) = message.document() {
+        attachment.kind = ActiveValue::Set(AttachmentKind::Document);
+        attachment.file_id = ActiveValue::Set(Some(document.file.id.clone()));
+    } else if let Some(location) = message.location() {
+        attachment.kind = ActiveValue::Set(AttachmentKind::Location);
+        attachment.latitude = ActiveValue::Set(Some(location.latitude));
+        attachment.longitude = ActiveValue::Set(Some(location.longitude));
+    } else {
+        return Ok(None);
+    }
+
+    Ok(Some(attachment.insert(connection).await?))
+}
+
+pub async fn remove_attachment(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    attachment_id: i32,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    emergency_attachments::Entity::delete_many()
+        .filter(emergency_attachments::Column::ChatId.eq(chat_id.0))
+        .filter(emergency_attachments::Column::Id.eq(attachment_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Re-wraps data keys of the sections and captions sealed with a retired
+// master key, returns the number of rotated rows
+pub async fn rotate_keys(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let rows = emergency_info::Entity::find()
+        .filter(emergency_info::Column::KeyId.ne(keyring.active_key_id()))
+        .all(connection)
+        .await?;
+
+    let mut rotated = 0;
+    for row in rows {
+        let sealed = Sealed {
+            ciphertext: row.ciphertext,
+            nonce: row.nonce,
+            data_key: row.data_key,
+            key_id: row.key_id,
+        };
+        let sealed = match keyring.rewrap(&sealed) {
+            Ok(sealed) => sealed,
+            Err(err) => {
+                log::error!("Can't rotate emergency info {}: {}", row.id, err);
+                continue;
+            }
+        };
+
+        emergency_info::ActiveModel {
+            id: ActiveValue::Unchanged(row.id),
+            data_key: ActiveValue::Set(sealed.data_key),
+            key_id: ActiveValue::Set(sealed.key_id),
+            ..Default::default()
+        }
+        .update(connection)
+        .await?;
+        rotated += 1;
+    }
+
+    let rows = emergency_attachments::Entity::find()
+        .filter(emergency_attachments::Column::CaptionKeyId.is_not_null())
+        .filter(emergency_attachments::Column::CaptionKeyId.ne(keyring.active_key_id()))
+        .all(connection)
+        .await?;
+    for row in rows {
+        let sealed = match get_sealed_caption(&row).map(|sealed| keyring.rewrap(&sealed)) {
+            Some(Ok(sealed)) => sealed,
+            Some(Err(err)) => {
+                log::error!("Can't rotate attachment caption {}: {}", row.id, err);
+                continue;
+            }
+            None => continue,
+        };
+
+        emergency_attachments::ActiveModel {
+            id: ActiveValue::Unchanged(row.id),
+            caption_data_key: ActiveValue::Set(Some(sealed.data_key)),
+            caption_key_id: ActiveValue::Set(Some(sealed.key_id)),
+            ..Default::default()
+        }
+        .update(connection)
+        .await?;
+        rotated += 1;
+    }
+
+    rotated += rotate_care_keys(connection, keyring).await?;
+
+    Ok(rotated)
+}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+pub enum DeliveryStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "sent")]
+    Sent,
+    // Out of attempts or the contact can't be reached at all
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+// One incident's alert for one contact, retried until it's sent or gives up
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "alert_deliveries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub incident_id: i32,
+    pub link_id: i32,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime,
+    pub created_at: DateTime,
+    pub sent_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+
+// A contact who used an invite code and waits for the owner's approval
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "contact_requests")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub primary_owner_chat_id: i64,
+    pub secondary_owner_chat_id: i64,
+    pub created_at: DateTime,
+    // The code the request came with, its use is spent on approval
+    pub invite: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum AttachmentKind {
+    #[sea_orm(num_value = 1)]
+    Photo,
+    #[sea_orm(num_value = 2)]
+    Document,
+    #[sea_orm(num_value = 3)]
+    Location,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "emergency_attachments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: i64,
+    pub kind: AttachmentKind,
+    pub file_id: Option<String>,
+    // The sealed caption, all four are set or none
+    pub caption_ciphertext: Option<Vec<u8>>,
+    pub caption_nonce: Option<Vec<u8>>,
+    pub caption_data_key: Option<Vec<u8>>,
+    pub caption_key_id: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
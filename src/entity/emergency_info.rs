@@ -1,4 +1,23 @@
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Also kept in the dialogue state while the owner types a section
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum EmergencySection {
+    #[sea_orm(num_value = 1)]
+    HomeAccess,
+    #[sea_orm(num_value = 2)]
+    Documents,
+    #[sea_orm(num_value = 3)]
+    Health,
+    #[sea_orm(num_value = 4)]
+    Diet,
+    #[sea_orm(num_value = 5)]
+    Instructions,
+    #[sea_orm(num_value = 6)]
+    Vet,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "emergency_info")]
@@ -6,7 +25,12 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub chat_id: i64,
-    pub text: String,
+    pub section: EmergencySection,
+    // Envelope encrypted text, see `crypto::Keyring`
+    pub ciphertext: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub data_key: Vec<u8>,
+    pub key_id: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum EscalationStage {
+    #[sea_orm(num_value = 0)]
+    None,
+    #[sea_orm(num_value = 1)]
+    Reminder,
+    #[sea_orm(num_value = 2)]
+    SecondReminder,
+    #[sea_orm(num_value = 3)]
+    HeadsUp,
+    #[sea_orm(num_value = 4)]
+    Alert,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "escalations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: i64,
+    pub stage: EscalationStage,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+use super::secondary_owners::ContactTier;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "incidents")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: i64,
+    pub created_at: DateTime,
+    pub resolved_at: Option<DateTime>,
+    pub acknowledged_by: Option<i64>,
+    pub acknowledged_at: Option<DateTime>,
+    // The last tier of contacts that got the alert
+    pub notified_tier: Option<ContactTier>,
+    pub notified_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -7,6 +7,10 @@ pub struct Model {
     pub id: i32,
     pub chat_id: i64,
     pub invite: String,
+    pub expires_at: DateTime,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub revoked: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
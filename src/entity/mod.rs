@@ -1,6 +1,17 @@
+pub mod alert_deliveries;
 pub mod alive_events;
+pub mod contact_requests;
+pub mod dialogues;
+pub mod emergency_attachments;
 pub mod emergency_info;
+pub mod escalations;
+pub mod health_nudges;
+pub mod incidents;
 pub mod invites;
+pub mod outbox_messages;
+pub mod pets;
 pub mod profiles;
+pub mod prompt_events;
 pub mod secondary_owners;
+pub mod settings;
 pub mod statuses;
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+
+pub use super::alert_deliveries::DeliveryStatus;
+
+// A bot message written together with the state change it belongs to and
+// sent later by the dispatcher. `chat_id` is the Telegram chat it ends up
+// in, if any, `link_id` is set for notices to contacts on any channel.
+// A check-in prompt counts as sent to the owner only once it's delivered.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "outbox_messages")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: Option<i64>,
+    pub link_id: Option<i32>,
+    pub payload: Json,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime,
+    pub created_at: DateTime,
+    pub sent_at: Option<DateTime>,
+    pub check_in_prompt: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "pets")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: i64,
+    pub name: String,
+    pub species: Option<String>,
+    pub photo_file_id: Option<String>,
+    // Medical notes, diet and vet contact sealed together, all four are set or none
+    pub care_ciphertext: Option<Vec<u8>>,
+    pub care_nonce: Option<Vec<u8>>,
+    pub care_data_key: Option<Vec<u8>>,
+    pub care_key_id: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,15 @@
 use sea_orm::entity::prelude::*;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+pub enum Language {
+    #[default]
+    #[sea_orm(string_value = "ru")]
+    Ru,
+    #[sea_orm(string_value = "en")]
+    En,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "profiles")]
 pub struct Model {
@@ -7,12 +17,18 @@ pub struct Model {
     pub id: i32,
     pub chat_id: i64,
     pub username: String,
+    pub time_zone: String,
+    pub language: Language,
+    // Set when Telegram says the bot is blocked or the account is gone
+    pub unreachable_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_one = "super::statuses::Entity")]
     MonitoringStatuses,
+    #[sea_orm(has_one = "super::settings::Entity")]
+    MonitoringSettings,
 }
 
 impl Related<super::statuses::Entity> for Entity {
@@ -21,4 +37,10 @@ impl Related<super::statuses::Entity> for Entity {
     }
 }
 
+impl Related<super::settings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MonitoringSettings.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,12 +1,41 @@
 use sea_orm::entity::prelude::*;
 
+// Contacts are alerted tier by tier, the next one only if nobody acknowledged
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum ContactTier {
+    #[sea_orm(num_value = 1)]
+    Primary,
+    #[sea_orm(num_value = 2)]
+    Secondary,
+    #[sea_orm(num_value = 3)]
+    Tertiary,
+}
+
+// How the contact gets alerted, everyone but Telegram contacts is reached by address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+pub enum ContactChannel {
+    #[default]
+    #[sea_orm(string_value = "telegram")]
+    Telegram,
+    #[sea_orm(string_value = "email")]
+    Email,
+    #[sea_orm(string_value = "sms")]
+    Sms,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "secondary_owners")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub primary_owner_chat_id: i64,
-    pub secondary_owner_chat_id: i64,
+    pub secondary_owner_chat_id: Option<i64>,
+    pub created_at: DateTime,
+    pub tier: ContactTier,
+    pub channel: ContactChannel,
+    pub address: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "monitoring_settings")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub chat_id: i64,
+    pub check_in_interval_hours: i32,
+    pub alert_grace_hours: i32,
+    pub check_in_window_start: Option<i32>,
+    pub check_in_window_end: Option<i32>,
+    pub quiet_hours_start: Option<i32>,
+    pub quiet_hours_end: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::profiles::Entity",
+        from = "Column::ChatId",
+        to = "super::profiles::Column::ChatId"
+    )]
+    Profiles,
+}
+
+impl Related<super::profiles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Profiles.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
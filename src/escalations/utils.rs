@@ -0,0 +1,190 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::prelude::*;
+use sea_orm::{sea_query::OnConflict, ActiveValue};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::config::Config;
+use crate::entity::{
+    escalations::{self, EscalationStage},
+    settings,
+};
+
+pub async fn get_stage<C: ConnectionTrait>(connection: &C, chat_id: ChatId) -> EscalationStage {
+    escalations::Entity::find()
+        .filter(escalations::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await
+        .ok()
+        .flatten()
+        .map_or(EscalationStage::None, |x| x.stage)
+}
+
+pub async fn set_stage<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    stage: EscalationStage,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    escalations::Entity::insert(escalations::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        stage: ActiveValue::Set(stage),
+        updated_at: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(escalations::Column::ChatId)
+            .update_columns([escalations::Column::Stage, escalations::Column::UpdatedAt])
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+// Returns the stage the ladder was at before the reset
+pub async fn reset_stage<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<EscalationStage, Box<dyn Error + Send + Sync>> {
+    let stage = get_stage(connection, chat_id).await;
+    if stage != EscalationStage::None {
+        escalations::Entity::delete_many()
+            .filter(escalations::Column::ChatId.eq(chat_id.0))
+            .exec(connection)
+            .await?;
+    }
+
+    Ok(stage)
+}
+
+// Stages scheduled at or after the grace period are skipped, the full alert always fires last
+pub fn get_due_stage(
+    config: &Config,
+    elapsed: Duration,
+    alert_grace_hours: i32,
+) -> EscalationStage {
+    let alert_grace_hours = alert_grace_hours as i64;
+    let ladder = [
+        (EscalationStage::HeadsUp, config.heads_up_after_hours),
+        (
+            EscalationStage::SecondReminder,
+            config.second_reminder_after_hours,
+        ),
+        (EscalationStage::Reminder, config.reminder_after_hours),
+    ];
+
+    if elapsed >= Duration::hours(alert_grace_hours) {
+        return EscalationStage::Alert;
+    }
+
+    ladder
+        .into_iter()
+        .filter(|(_, hours)| *hours < alert_grace_hours)
+        .find(|(_, hours)| elapsed >= Duration::hours(*hours))
+        .map_or(EscalationStage::None, |(stage, _)| stage)
+}
+
+// When the full alert fires if the owner stays silent from now on. The prompt
+// can be held back by the check-in window, so it's the earliest possible time.
+pub fn get_next_alert_at(
+    settings: &settings::Model,
+    last_seen_at: Option<NaiveDateTime>,
+    prompted_at: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> NaiveDateTime {
+    let grace = Duration::hours(settings.alert_grace_hours as i64);
+    let alert_at = match (last_seen_at, prompted_at) {
+        (Some(last_seen_at), Some(prompted_at)) if prompted_at > last_seen_at => {
+            prompted_at + grace
+        }
+        (None, Some(prompted_at)) => prompted_at + grace,
+        (Some(last_seen_at), _) => {
+            let prompt_at = last_seen_at + Duration::hours(settings.check_in_interval_hours as i64);
+            prompt_at.max(now) + grace
+        }
+        // The first prompt goes out on the next run
+        (None, None) => now + grace,
+    };
+    // An overdue alert goes out on the next run
+    alert_at.max(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Transport;
+    use crate::crypto::Keyring;
+
+    // Reminder at 6h, second reminder at 12h and heads-up at 18h
+    fn get_config() -> Config {
+        Config {
+            db_url: String::new(),
+            sentry_url: String::new(),
+            transport: Transport::Polling,
+            reminder_after_hours: 6,
+            second_reminder_after_hours: 12,
+            heads_up_after_hours: 18,
+            alert_tier_wait_minutes: 60,
+            health_nudge_interval_hours: 72,
+            scheduler_enabled: false,
+            scheduler_interval_minutes: 10,
+            keyring: Keyring::parse("a", "a:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=").unwrap(),
+            smtp: None,
+            sms_gateway: None,
+        }
+    }
+
+    #[test]
+    fn due_stage_follows_the_ladder() {
+        let config = get_config();
+        // (elapsed minutes, grace hours, expected stage)
+        let cases = [
+            (0, 24, EscalationStage::None),
+            (6 * 60 - 1, 24, EscalationStage::None),
+            (6 * 60, 24, EscalationStage::Reminder),
+            (12 * 60 - 1, 24, EscalationStage::Reminder),
+            (12 * 60, 24, EscalationStage::SecondReminder),
+            (18 * 60, 24, EscalationStage::HeadsUp),
+            (24 * 60 - 1, 24, EscalationStage::HeadsUp),
+            (24 * 60, 24, EscalationStage::Alert),
+            (100 * 60, 24, EscalationStage::Alert),
+        ];
+        for (elapsed, grace, stage) in cases {
+            assert_eq!(
+                get_due_stage(&config, Duration::minutes(elapsed), grace),
+                stage,
+                "{} minutes with {}h grace",
+                elapsed,
+                grace
+            );
+        }
+    }
+
+    #[test]
+    fn due_stage_skips_stages_at_or_after_the_grace_period() {
+        let config = get_config();
+        let cases = [
+            // The heads-up would go out together with the alert
+            (17 * 60, 18, EscalationStage::SecondReminder),
+            (18 * 60, 18, EscalationStage::Alert),
+            // Only the first reminder fits before a 12h grace
+            (11 * 60, 12, EscalationStage::Reminder),
+            (12 * 60, 12, EscalationStage::Alert),
+            // Nothing fits, the owner goes straight to the alert
+            (5 * 60, 6, EscalationStage::None),
+            (6 * 60, 6, EscalationStage::Alert),
+            (0, 4, EscalationStage::None),
+            (4 * 60, 4, EscalationStage::Alert),
+        ];
+        for (elapsed, grace, stage) in cases {
+            assert_eq!(
+                get_due_stage(&config, Duration::minutes(elapsed), grace),
+                stage,
+                "{} minutes with {}h grace",
+                elapsed,
+                grace
+            );
+        }
+    }
+}
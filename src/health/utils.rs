@@ -0,0 +1,124 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::prelude::*;
+use sea_orm::{sea_query::OnConflict, ActiveValue, JoinType, QuerySelect};
+use serde::Serialize;
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::entity::{
+    emergency_info, health_nudges, profiles,
+    secondary_owners::{self, ContactChannel},
+    statuses,
+};
+use crate::profiles::utils::{select_emergency_contacts, select_profile};
+
+// Reasons an alert wouldn't help the owner, in the order they're shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthProblem {
+    NoContacts,
+    ContactsUnreachable,
+    NoEmergencyInfo,
+}
+
+// Contacts off Telegram are taken on trust, their deliveries report failures
+async fn is_contact_reachable(
+    connection: &DatabaseConnection,
+    link: &secondary_owners::Model,
+) -> bool {
+    let chat_id = match (link.channel, link.secondary_owner_chat_id) {
+        (ContactChannel::Telegram, Some(chat_id)) => ChatId(chat_id),
+        _ => return true,
+    };
+    select_profile(chat_id)
+        .one(connection)
+        .await
+        .ok()
+        .flatten()
+        .map_or(true, |x| x.unreachable_at.is_none())
+}
+
+pub async fn get_health_problems(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+) -> Result<Vec<HealthProblem>, Box<dyn Error + Send + Sync>> {
+    let mut problems = vec![];
+
+    let contacts = select_emergency_contacts(chat_id).all(connection).await?;
+    let mut reachable = false;
+    for contact in &contacts {
+        if is_contact_reachable(connection, contact).await {
+            reachable = true;
+            break;
+        }
+    }
+    if contacts.is_empty() {
+        problems.push(HealthProblem::NoContacts);
+    } else if !reachable {
+        problems.push(HealthProblem::ContactsUnreachable);
+    }
+
+    let sections = emergency_info::Entity::find()
+        .filter(emergency_info::Column::ChatId.eq(chat_id.0))
+        .count(connection)
+        .await?;
+    if sections == 0 {
+        problems.push(HealthProblem::NoEmergencyInfo);
+    }
+
+    Ok(problems)
+}
+
+// Unlike `select_active_profiles`, owners without contacts are included,
+// they are the ones who most need to hear about it
+pub fn select_monitored_profiles() -> Select<profiles::Entity> {
+    profiles::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            profiles::Relation::MonitoringStatuses.def(),
+        )
+        .filter(statuses::Column::Enabled.eq(true))
+        .filter(profiles::Column::UnreachableAt.is_null())
+        // `/start` turns monitoring on for everybody, contacts who joined
+        // through an invite and never set up anything for themselves are skipped
+        .filter(Expr::cust(
+            "(NOT EXISTS (SELECT 1 FROM secondary_owners AS backed_up \
+             WHERE backed_up.secondary_owner_chat_id = profiles.chat_id) \
+             OR EXISTS (SELECT 1 FROM secondary_owners AS contacts \
+             WHERE contacts.primary_owner_chat_id = profiles.chat_id) \
+             OR EXISTS (SELECT 1 FROM emergency_info \
+             WHERE emergency_info.chat_id = profiles.chat_id) \
+             OR EXISTS (SELECT 1 FROM pets WHERE pets.chat_id = profiles.chat_id))",
+        ))
+}
+
+pub async fn get_last_nudge(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+) -> Result<Option<NaiveDateTime>, Box<dyn Error + Send + Sync>> {
+    Ok(health_nudges::Entity::find()
+        .filter(health_nudges::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await?
+        .map(|x| x.timestamp))
+}
+
+pub async fn mark_nudged<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    health_nudges::Entity::insert(health_nudges::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        timestamp: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(health_nudges::Column::ChatId)
+            .update_column(health_nudges::Column::Timestamp)
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
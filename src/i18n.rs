@@ -0,0 +1,497 @@
+use sea_orm::ActiveEnum;
+use tera::{Context, Tera};
+
+use crate::entity::profiles::Language;
+
+pub const LANGUAGES: [(Language, &str); 2] =
+    [(Language::Ru, "🇷🇺 Русский"), (Language::En, "🇬🇧 English")];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Text {
+    CommandNotFound,
+    NoContacts,
+    InviteCodeError,
+    PetOwner,
+    EnableMonitoringButton,
+    DisableMonitoringButton,
+    OwnerMenuButton,
+    ContactMenuButton,
+    StartOwnerMenuButton,
+    StartContactMenuButton,
+    EmergencyInfoButton,
+    MonitoringSettingsButton,
+    AcceptInviteButton,
+    AliveButton,
+    CheckInPrompt,
+    AskForInvite,
+    UnknownInvite,
+    ExpiredInvite,
+    UsedInvite,
+    RevokedInvite,
+    InviteAccepted,
+    InviteRejected,
+    SelfInvite,
+    AlreadyLinked,
+    ContactRequest,
+    ContactRequestSent,
+    ContactRequestNotFound,
+    ContactRequestInviteUnavailable,
+    TooManyContactRequests,
+    ContactApproved,
+    ContactRejected,
+    ApproveContactButton,
+    RejectContactButton,
+    ContactSince,
+    ContactTierHint,
+    TierPrimary,
+    TierSecondary,
+    TierTertiary,
+    ContactNotFound,
+    ContactRemoved,
+    OwnerRemoved,
+    RemoveContactButton,
+    AddExternalContactButton,
+    AskForExternalContact,
+    InvalidContactAddress,
+    ExternalContactsUnavailable,
+    EmailUnavailable,
+    SmsUnavailable,
+    ExternalContactAdded,
+    ExternalContactExists,
+    ExternalContactHint,
+    NotificationSubject,
+    ContactsUnreachable,
+    ContactUnreachable,
+    LeaveOwnerButton,
+    ConfirmLeaveOwner,
+    ConfirmLeaveOwnerButton,
+    InviteQrCodeButton,
+    RegenerateInviteButton,
+    RevokeInviteButton,
+    AcknowledgeButton,
+    AcknowledgeForButton,
+    IncidentLine,
+    IncidentAcknowledgedBy,
+    IncidentNotAcknowledged,
+    IncidentResolved,
+    IncidentNotAlerted,
+    AlreadyAcknowledgedByYou,
+    AlreadyAcknowledgedBy,
+    AcknowledgeThanks,
+    Hours,
+    AnyTime,
+    NoQuietHours,
+    TimeZoneButton,
+    UnknownTimeZone,
+    ChooseLanguage,
+    LanguageChanged,
+    PetsButton,
+    AddPetButton,
+    BackToPetsButton,
+    EditPetNameButton,
+    EditPetSpeciesButton,
+    EditPetPhotoButton,
+    EditPetMedicalNotesButton,
+    EditPetDietButton,
+    EditPetVetContactButton,
+    RemovePetButton,
+    ConfirmRemovePetButton,
+    CancelButton,
+    ConfirmRemovePet,
+    AskForPetName,
+    AskForPetSpecies,
+    AskForPetPhoto,
+    AskForPetMedicalNotes,
+    AskForPetDiet,
+    AskForPetVetContact,
+    ExpectedPetPhoto,
+    ExpectedText,
+    PetNotFound,
+    FillEmergencyInfoButton,
+    SkipButton,
+    BackButton,
+    EmergencyHomeAccess,
+    EmergencyDocuments,
+    EmergencyHealth,
+    EmergencyDiet,
+    EmergencyInstructions,
+    EmergencyVet,
+    AskForEmergencyHomeAccess,
+    AskForEmergencyDocuments,
+    AskForEmergencyHealth,
+    AskForEmergencyDiet,
+    AskForEmergencyInstructions,
+    AskForEmergencyVet,
+    EmergencyStep,
+    ClearSectionHint,
+    AddAttachmentButton,
+    AskForAttachment,
+    AttachmentSaved,
+    ExpectedAttachment,
+    AttachmentPhoto,
+    AttachmentDocument,
+    AttachmentLocation,
+}
+
+fn ru(text: Text) -> &'static str {
+    match text {
+        Text::CommandNotFound => "Команда не найдена",
+        Text::NoContacts => "Нет контактов",
+        Text::InviteCodeError => "Ошибка",
+        Text::PetOwner => "Владелец питомца",
+        Text::EnableMonitoringButton => "▶️ Включить мониторинг",
+        Text::DisableMonitoringButton => "⏸ Выключить мониторинг",
+        Text::OwnerMenuButton => "👈 Меню владельца питомца",
+        Text::ContactMenuButton => "👈 Меню резервного контакта",
+        Text::StartOwnerMenuButton => "🐶 Меню для владельцев питомцев",
+        Text::StartContactMenuButton => "🛟 Меню для резервных контактов",
+        Text::EmergencyInfoButton => "⚠️️ Экстренная информация",
+        Text::MonitoringSettingsButton => "⏱ Настройки мониторинга",
+        Text::AcceptInviteButton => "🤝 Принять приглашение",
+        Text::AliveButton => "👍 Все хорошо",
+        Text::CheckInPrompt => "Пожалуйста подтвердите, что с вами все хорошо 🙏",
+        Text::AskForInvite => "Пожалуйста отправьте код приглашения следующим сообщением.",
+        Text::UnknownInvite => "Неизвестный код приглашения.",
+        Text::ExpiredInvite => {
+            "Срок действия кода приглашения истек. Попросите владельца питомца прислать новый."
+        }
+        Text::UsedInvite => {
+            "Этот код приглашения уже использован. Попросите владельца питомца прислать новый."
+        }
+        Text::RevokedInvite => "Владелец питомца отозвал этот код приглашения.",
+        Text::InviteAccepted => "Готово! Теперь вы резервный контакт {username}.",
+        Text::SelfInvite => {
+            "Это ваш собственный код приглашения. Отправьте его человеку, который будет вас подстраховывать."
+        }
+        Text::AlreadyLinked => "Вы уже резервный контакт {username}.",
+        Text::InviteRejected => "Ваша заявка на роль резервного контакта {username} отклонена.",
+        Text::ContactRequest => {
+            "{contact} хочет стать вашим резервным контактом. \
+             Если вы не выйдете на связь, этот человек получит вашу экстренную информацию. \
+             Одобрить?"
+        }
+        Text::ContactRequestSent => {
+            "Заявка отправлена. Вы станете резервным контактом, как только {username} ее одобрит."
+        }
+        Text::ContactRequestNotFound => "Эта заявка уже обработана.",
+        Text::ContactRequestInviteUnavailable => {
+            "Код приглашения из этой заявки уже использован, отозван или заменен. \
+             Отправьте этому человеку новый код или отклоните заявку."
+        }
+        Text::TooManyContactRequests => {
+            "У владельца питомца слишком много необработанных заявок по этому коду. \
+             Попробуйте позже или попросите новый код."
+        }
+        Text::ContactApproved => "{contact} теперь ваш резервный контакт.",
+        Text::ContactRejected => "Заявка {contact} отклонена.",
+        Text::ApproveContactButton => "✅ Одобрить",
+        Text::RejectContactButton => "❌ Отклонить",
+        Text::ContactSince => "{contact} — ваш резервный контакт с {since}.",
+        Text::ContactTierHint => {
+            "При тревоге сначала оповещаем первую очередь. \
+             Следующая очередь получит тревогу, только если никто не откликнулся."
+        }
+        Text::TierPrimary => "1️⃣ Первая очередь",
+        Text::TierSecondary => "2️⃣ Вторая очередь",
+        Text::TierTertiary => "3️⃣ Третья очередь",
+        Text::ContactNotFound => "Этой связи уже нет.",
+        Text::ContactRemoved => "{contact} больше не ваш резервный контакт.",
+        Text::OwnerRemoved => "Вы больше не резервный контакт {username}.",
+        Text::RemoveContactButton => "🗑 Удалить контакт",
+        Text::AddExternalContactButton => "✉️ Добавить контакт по email или телефону",
+        Text::AskForExternalContact => {
+            "Отправьте email или номер телефона в международном формате, \
+             например +7 912 345-67-89. Тревоги будут приходить туда письмом или SMS."
+        }
+        Text::InvalidContactAddress => {
+            "Это не похоже на email или номер телефона в международном формате."
+        }
+        Text::ExternalContactsUnavailable => "Контакты по email и SMS на этом боте не настроены.",
+        Text::EmailUnavailable => "Этот бот не умеет отправлять email.",
+        Text::SmsUnavailable => "Этот бот не умеет отправлять SMS.",
+        Text::ExternalContactAdded => {
+            "{contact} теперь ваш резервный контакт. Тревоги будут приходить туда."
+        }
+        Text::ExternalContactExists => "{contact} уже есть среди ваших резервных контактов.",
+        Text::ExternalContactHint => {
+            "Из письма или SMS нельзя откликнуться на тревогу, \
+             поэтому следующая очередь получит ее в любом случае."
+        }
+        Text::NotificationSubject => "Trusty Tail: новости о {username}",
+        Text::ContactsUnreachable => {
+            "⚠️ Не удалось отправить тревогу резервным контактам: {contacts}. \
+             Проверьте, что бот не заблокирован и адреса указаны верно."
+        }
+        Text::ContactUnreachable => {
+            "⚠️ Бот больше не может написать {contact}: он заблокирован или аккаунт удалён. \
+             Тревоги не дойдут, пока контакт снова не напишет боту. \
+             Попросите его об этом или добавьте другой резервный контакт."
+        }
+        Text::LeaveOwnerButton => "🚪 Перестать подстраховывать {username}",
+        Text::ConfirmLeaveOwner => {
+            "Перестать подстраховывать {username}? Вы больше не будете получать тревоги."
+        }
+        Text::ConfirmLeaveOwnerButton => "🚪 Да, перестать",
+        Text::InviteQrCodeButton => "📷 QR-код приглашения",
+        Text::RegenerateInviteButton => "🔄 Новый код приглашения",
+        Text::RevokeInviteButton => "🚫 Отозвать код",
+        Text::AcknowledgeButton => "🙋 Я займусь",
+        Text::AcknowledgeForButton => "🙋 Я займусь: {username}",
+        Text::IncidentLine => "🚨 {username} (с {since}), {status}",
+        Text::IncidentAcknowledgedBy => "занимается {contact}",
+        Text::IncidentNotAcknowledged => "никто пока не занимается",
+        Text::IncidentResolved => "Тревога уже снята, владелец питомца вышел на связь.",
+        Text::IncidentNotAlerted => {
+            "Вас не оповещали об этой тревоге, ей занимаются контакты первой очереди."
+        }
+        Text::AlreadyAcknowledgedByYou => "Вы уже взяли это на себя.",
+        Text::AlreadyAcknowledgedBy => "{contact} уже занимается этим.",
+        Text::AcknowledgeThanks => {
+            "Спасибо! Мы сообщили владельцу питомца и остальным резервным контактам."
+        }
+        Text::Hours => "{hours} ч.",
+        Text::AnyTime => "Любое время",
+        Text::NoQuietHours => "Без тихих часов",
+        Text::TimeZoneButton => "🌍 Часовой пояс",
+        Text::UnknownTimeZone => "Неизвестный часовой пояс.",
+        Text::ChooseLanguage => "Выберите язык:",
+        Text::LanguageChanged => "Язык изменен на русский.",
+        Text::PetsButton => "🐾 Питомцы",
+        Text::AddPetButton => "➕ Добавить питомца",
+        Text::BackToPetsButton => "👈 Питомцы",
+        Text::EditPetNameButton => "✏️ Кличка",
+        Text::EditPetSpeciesButton => "🐕 Вид",
+        Text::EditPetPhotoButton => "📷 Фото",
+        Text::EditPetMedicalNotesButton => "💊 Здоровье",
+        Text::EditPetDietButton => "🥣 Питание",
+        Text::EditPetVetContactButton => "🩺 Ветеринар",
+        Text::RemovePetButton => "🗑 Удалить",
+        Text::ConfirmRemovePetButton => "🗑 Да, удалить",
+        Text::CancelButton => "Отмена",
+        Text::ConfirmRemovePet => "Удалить питомца {name}? Резервные контакты больше не получат информацию о нем.",
+        Text::AskForPetName => "Как зовут питомца? Отправьте кличку следующим сообщением.",
+        Text::AskForPetSpecies => "Кто это? Например: кошка, собака, попугай. Отправьте «-», чтобы очистить.",
+        Text::AskForPetPhoto => "Отправьте фото питомца, чтобы резервные контакты могли его узнать. Отправьте «-», чтобы удалить фото.",
+        Text::AskForPetMedicalNotes => "Опишите здоровье питомца: болезни, лекарства, прививки. Отправьте «-», чтобы очистить.",
+        Text::AskForPetDiet => "Чем и как часто кормить питомца? Отправьте «-», чтобы очистить.",
+        Text::AskForPetVetContact => "Отправьте контакты ветеринара. Отправьте «-», чтобы очистить.",
+        Text::ExpectedPetPhoto => "Пожалуйста, отправьте фото или «-».",
+        Text::ExpectedText => "Пожалуйста, отправьте текст.",
+        Text::PetNotFound => "Питомец не найден.",
+        Text::FillEmergencyInfoButton => "✍️ Заполнить по шагам",
+        Text::SkipButton => "⏭ Пропустить",
+        Text::BackButton => "👈 Назад",
+        Text::EmergencyHomeAccess => "🏠 Доступ в дом",
+        Text::EmergencyDocuments => "📄 Документы на питомца",
+        Text::EmergencyHealth => "💊 Здоровье питомца",
+        Text::EmergencyDiet => "🥣 Рекомендованная диета",
+        Text::EmergencyInstructions => "📝 Особые инструкции",
+        Text::EmergencyVet => "🩺 Ветеринар",
+        Text::AskForEmergencyHomeAccess => "Как резервный контакт может попасть в дом? Например, телефон родственника или арендодателя, где лежит ключ.",
+        Text::AskForEmergencyDocuments => "Где лежат все необходимые документы на питомца?",
+        Text::AskForEmergencyHealth => "Есть ли у питомца заболевания или особые потребности в уходе?",
+        Text::AskForEmergencyDiet => "Чем кормить питомца? Укажите предпочтения в еде и ограничения.",
+        Text::AskForEmergencyInstructions => "Есть ли особые указания по уходу? Например, прогулки, игрушки, способы успокоения.",
+        Text::AskForEmergencyVet => "Контактные данные ветеринара для экстренной помощи.",
+        Text::EmergencyStep => "Шаг {step} из {total}. {title}",
+        Text::ClearSectionHint => "Отправьте «-», чтобы очистить раздел. Фото, документы и геопозицию можно прислать на любом шаге.",
+        Text::AddAttachmentButton => "📎 Добавить вложение",
+        Text::AskForAttachment => "Отправьте фото, документ или геопозицию, например фото ключницы, справку о прививках или точку на карте. Подпись к файлу тоже сохранится.",
+        Text::AttachmentSaved => "📎 Вложение сохранено.",
+        Text::ExpectedAttachment => "Пожалуйста, отправьте фото, документ или геопозицию.",
+        Text::AttachmentPhoto => "📷 Фото",
+        Text::AttachmentDocument => "📄 Документ",
+        Text::AttachmentLocation => "📍 Геопозиция",
+    }
+}
+
+fn en(text: Text) -> &'static str {
+    match text {
+        Text::CommandNotFound => "Command not found",
+        Text::NoContacts => "No contacts",
+        Text::InviteCodeError => "Error",
+        Text::PetOwner => "The pet owner",
+        Text::EnableMonitoringButton => "▶️ Turn monitoring on",
+        Text::DisableMonitoringButton => "⏸ Turn monitoring off",
+        Text::OwnerMenuButton => "👈 Pet owner menu",
+        Text::ContactMenuButton => "👈 Backup contact menu",
+        Text::StartOwnerMenuButton => "🐶 Menu for pet owners",
+        Text::StartContactMenuButton => "🛟 Menu for backup contacts",
+        Text::EmergencyInfoButton => "⚠️️ Emergency info",
+        Text::MonitoringSettingsButton => "⏱ Monitoring settings",
+        Text::AcceptInviteButton => "🤝 Accept an invite",
+        Text::AliveButton => "👍 I'm fine",
+        Text::CheckInPrompt => "Please confirm that you're fine 🙏",
+        Text::AskForInvite => "Please send the invite code in your next message.",
+        Text::UnknownInvite => "Unknown invite code.",
+        Text::ExpiredInvite => "This invite code has expired. Ask the pet owner for a new one.",
+        Text::UsedInvite => "This invite code has already been used. Ask the pet owner for a new one.",
+        Text::RevokedInvite => "The pet owner has revoked this invite code.",
+        Text::InviteAccepted => "Done! You are now a backup contact of {username}.",
+        Text::SelfInvite => {
+            "This is your own invite code. Send it to the person who will back you up."
+        }
+        Text::AlreadyLinked => "You are already a backup contact of {username}.",
+        Text::InviteRejected => "{username} declined your request to become a backup contact.",
+        Text::ContactRequest => {
+            "{contact} wants to become your backup contact. \
+             If you go silent, they will receive your emergency info. \
+             Approve?"
+        }
+        Text::ContactRequestSent => {
+            "Request sent. You'll become a backup contact as soon as {username} approves it."
+        }
+        Text::ContactRequestNotFound => "This request has already been handled.",
+        Text::ContactRequestInviteUnavailable => {
+            "The invite code from this request has been used up, revoked or replaced. \
+             Send this person a new code or decline the request."
+        }
+        Text::TooManyContactRequests => {
+            "The pet owner has too many pending requests for this code. \
+             Try again later or ask them for a new one."
+        }
+        Text::ContactApproved => "{contact} is now your backup contact.",
+        Text::ContactRejected => "Request from {contact} declined.",
+        Text::ApproveContactButton => "✅ Approve",
+        Text::RejectContactButton => "❌ Decline",
+        Text::ContactSince => "{contact} has been your backup contact since {since}.",
+        Text::ContactTierHint => {
+            "In an emergency the first tier is alerted first. \
+             The next tier gets the alert only if nobody responded."
+        }
+        Text::TierPrimary => "1️⃣ First tier",
+        Text::TierSecondary => "2️⃣ Second tier",
+        Text::TierTertiary => "3️⃣ Third tier",
+        Text::ContactNotFound => "This link no longer exists.",
+        Text::ContactRemoved => "{contact} is no longer your backup contact.",
+        Text::OwnerRemoved => "You are no longer a backup contact of {username}.",
+        Text::RemoveContactButton => "🗑 Remove contact",
+        Text::AddExternalContactButton => "✉️ Add contact by email or phone",
+        Text::AskForExternalContact => {
+            "Send an email address or a phone number in the international format, \
+             e.g. +44 20 7946 0958. Alerts will go there by email or SMS."
+        }
+        Text::InvalidContactAddress => {
+            "That doesn't look like an email address or an international phone number."
+        }
+        Text::ExternalContactsUnavailable => "Email and SMS contacts aren't set up on this bot.",
+        Text::EmailUnavailable => "This bot can't send emails.",
+        Text::SmsUnavailable => "This bot can't send SMS.",
+        Text::ExternalContactAdded => "{contact} is now your backup contact, alerts will go there.",
+        Text::ExternalContactExists => "{contact} is already one of your backup contacts.",
+        Text::ExternalContactHint => {
+            "An alert can't be answered from an email or SMS, \
+             so the next tier will get it either way."
+        }
+        Text::NotificationSubject => "Trusty Tail: news about {username}",
+        Text::ContactsUnreachable => {
+            "⚠️ We couldn't deliver the alert to these backup contacts: {contacts}. \
+             Check that they haven't blocked the bot and that their addresses are right."
+        }
+        Text::ContactUnreachable => {
+            "⚠️ The bot can't reach {contact} anymore: it was blocked or the account was deleted. \
+             Alerts won't get through until they message the bot again. \
+             Ask them to, or add another backup contact."
+        }
+        Text::LeaveOwnerButton => "🚪 Stop backing up {username}",
+        Text::ConfirmLeaveOwner => "Stop backing up {username}? You won't receive their alerts anymore.",
+        Text::ConfirmLeaveOwnerButton => "🚪 Yes, stop",
+        Text::InviteQrCodeButton => "📷 Invite QR code",
+        Text::RegenerateInviteButton => "🔄 New invite code",
+        Text::RevokeInviteButton => "🚫 Revoke code",
+        Text::AcknowledgeButton => "🙋 I'll handle it",
+        Text::AcknowledgeForButton => "🙋 I'll handle it: {username}",
+        Text::IncidentLine => "🚨 {username} (since {since}), {status}",
+        Text::IncidentAcknowledgedBy => "{contact} is on it",
+        Text::IncidentNotAcknowledged => "nobody is on it yet",
+        Text::IncidentResolved => "The alert is already over, the pet owner is back in touch.",
+        Text::IncidentNotAlerted => {
+            "You weren't alerted about this incident, the contacts before you in line are on it."
+        }
+        Text::AlreadyAcknowledgedByYou => "You've already taken this on.",
+        Text::AlreadyAcknowledgedBy => "{contact} is already on it.",
+        Text::AcknowledgeThanks => {
+            "Thank you! We've let the pet owner and the other backup contacts know."
+        }
+        Text::Hours => "{hours} h",
+        Text::AnyTime => "Any time",
+        Text::NoQuietHours => "No quiet hours",
+        Text::TimeZoneButton => "🌍 Time zone",
+        Text::UnknownTimeZone => "Unknown time zone.",
+        Text::ChooseLanguage => "Choose your language:",
+        Text::LanguageChanged => "Language changed to English.",
+        Text::PetsButton => "🐾 Pets",
+        Text::AddPetButton => "➕ Add a pet",
+        Text::BackToPetsButton => "👈 Pets",
+        Text::EditPetNameButton => "✏️ Name",
+        Text::EditPetSpeciesButton => "🐕 Species",
+        Text::EditPetPhotoButton => "📷 Photo",
+        Text::EditPetMedicalNotesButton => "💊 Health",
+        Text::EditPetDietButton => "🥣 Diet",
+        Text::EditPetVetContactButton => "🩺 Vet",
+        Text::RemovePetButton => "🗑 Remove",
+        Text::ConfirmRemovePetButton => "🗑 Yes, remove",
+        Text::CancelButton => "Cancel",
+        Text::ConfirmRemovePet => "Remove {name}? Backup contacts won't get their info anymore.",
+        Text::AskForPetName => "What's your pet's name? Send it in your next message.",
+        Text::AskForPetSpecies => "What kind of pet is it? For example: cat, dog, parrot. Send \"-\" to clear.",
+        Text::AskForPetPhoto => "Send a photo of your pet so backup contacts can recognize them. Send \"-\" to remove the photo.",
+        Text::AskForPetMedicalNotes => "Describe your pet's health: illnesses, medication, vaccinations. Send \"-\" to clear.",
+        Text::AskForPetDiet => "What and how often should your pet be fed? Send \"-\" to clear.",
+        Text::AskForPetVetContact => "Send your vet's contact details. Send \"-\" to clear.",
+        Text::ExpectedPetPhoto => "Please send a photo or \"-\".",
+        Text::ExpectedText => "Please send some text.",
+        Text::PetNotFound => "Pet not found.",
+        Text::FillEmergencyInfoButton => "✍️ Fill in step by step",
+        Text::SkipButton => "⏭ Skip",
+        Text::BackButton => "👈 Back",
+        Text::EmergencyHomeAccess => "🏠 Home access",
+        Text::EmergencyDocuments => "📄 Pet documents",
+        Text::EmergencyHealth => "💊 Pet health",
+        Text::EmergencyDiet => "🥣 Recommended diet",
+        Text::EmergencyInstructions => "📝 Special instructions",
+        Text::EmergencyVet => "🩺 Vet",
+        Text::AskForEmergencyHomeAccess => "How can a backup contact get into your home? For example, a relative's or landlord's phone number, where the key is.",
+        Text::AskForEmergencyDocuments => "Where are all of your pet's documents?",
+        Text::AskForEmergencyHealth => "Does your pet have any illnesses or special care needs?",
+        Text::AskForEmergencyDiet => "What should your pet be fed? Mention food preferences and restrictions.",
+        Text::AskForEmergencyInstructions => "Any special care instructions? For example, walks, toys, ways to calm them down.",
+        Text::AskForEmergencyVet => "Your vet's contact details for emergencies.",
+        Text::EmergencyStep => "Step {step} of {total}. {title}",
+        Text::ClearSectionHint => "Send \"-\" to clear the section. You can send photos, documents and locations at any step.",
+        Text::AddAttachmentButton => "📎 Add an attachment",
+        Text::AskForAttachment => "Send a photo, a document or a location, e.g. a photo of the key box, vaccination records or a map pin. The file caption is saved too.",
+        Text::AttachmentSaved => "📎 Attachment saved.",
+        Text::ExpectedAttachment => "Please send a photo, a document or a location.",
+        Text::AttachmentPhoto => "📷 Photo",
+        Text::AttachmentDocument => "📄 Document",
+        Text::AttachmentLocation => "📍 Location",
+    }
+}
+
+impl Text {
+    pub fn get(self, language: Language) -> &'static str {
+        match language {
+            Language::Ru => ru(self),
+            Language::En => en(self),
+        }
+    }
+
+    // Fills `{name}` placeholders of the translated text
+    pub fn format(self, language: Language, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.get(language).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+// Templates live in one directory per language, e.g. `templates/en/start.html`
+pub fn render(
+    tera: &Tera,
+    language: Language,
+    name: &str,
+    context: &Context,
+) -> tera::Result<String> {
+    tera.render(&format!("{}/{}", language.to_value(), name), context)
+}
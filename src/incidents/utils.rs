@@ -0,0 +1,152 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::prelude::*;
+use sea_orm::{ActiveValue, JoinType, QueryOrder, QuerySelect};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::entity::{
+    incidents,
+    secondary_owners::{self, ContactTier},
+};
+use crate::profiles::utils::select_emergency_contacts;
+
+pub fn select_open_incident(chat_id: ChatId) -> Select<incidents::Entity> {
+    incidents::Entity::find()
+        .filter(incidents::Column::ChatId.eq(chat_id.0))
+        .filter(incidents::Column::ResolvedAt.is_null())
+}
+
+pub async fn open_incident<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<incidents::Model, Box<dyn Error + Send + Sync>> {
+    if let Some(incident) = select_open_incident(chat_id).one(connection).await? {
+        return Ok(incident);
+    }
+
+    let incident = incidents::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    Ok(incident)
+}
+
+// Returns the incident that got resolved, if there was an open one
+pub async fn resolve_incident<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<Option<incidents::Model>, Box<dyn Error + Send + Sync>> {
+    let incident = match select_open_incident(chat_id).one(connection).await? {
+        Some(incident) => incident,
+        None => return Ok(None),
+    };
+
+    let mut incident: incidents::ActiveModel = incident.into();
+    incident.resolved_at = ActiveValue::Set(Some(Utc::now().naive_utc()));
+    let incident = incident.update(connection).await?;
+
+    Ok(Some(incident))
+}
+
+// Open incidents the contact was alerted about, same as `select_notified_contacts`
+pub fn select_open_incidents_for_contact(chat_id: ChatId) -> Select<incidents::Entity> {
+    incidents::Entity::find()
+        .join_rev(
+            JoinType::InnerJoin,
+            secondary_owners::Entity::belongs_to(incidents::Entity)
+                .from(secondary_owners::Column::PrimaryOwnerChatId)
+                .to(incidents::Column::ChatId)
+                .into(),
+        )
+        .filter(secondary_owners::Column::SecondaryOwnerChatId.eq(chat_id.0))
+        // NULL until the first tier is alerted, which matches nobody
+        .filter(
+            Expr::col((secondary_owners::Entity, secondary_owners::Column::Tier)).lte(Expr::col((
+                incidents::Entity,
+                incidents::Column::NotifiedTier,
+            ))),
+        )
+        .filter(incidents::Column::ResolvedAt.is_null())
+        .order_by_asc(incidents::Column::CreatedAt)
+}
+
+// Only the first contact gets to acknowledge, returns whether it was this one
+pub async fn acknowledge_incident<C: ConnectionTrait>(
+    connection: &C,
+    incident_id: i32,
+    chat_id: ChatId,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let result = incidents::Entity::update_many()
+        .set(incidents::ActiveModel {
+            acknowledged_by: ActiveValue::Set(Some(chat_id.0)),
+            acknowledged_at: ActiveValue::Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        })
+        .filter(incidents::Column::Id.eq(incident_id))
+        .filter(incidents::Column::ResolvedAt.is_null())
+        .filter(incidents::Column::AcknowledgedBy.is_null())
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+// Contacts that already got the alert for the incident
+pub fn select_notified_contacts(incident: &incidents::Model) -> Select<secondary_owners::Entity> {
+    let contacts = select_emergency_contacts(ChatId(incident.chat_id));
+    match incident.notified_tier {
+        Some(tier) => contacts.filter(secondary_owners::Column::Tier.lte(tier)),
+        None => contacts.filter(Expr::value(false)),
+    }
+}
+
+// The closest tier after the notified one that has anybody in it
+pub async fn get_next_tier<C: ConnectionTrait>(
+    connection: &C,
+    incident: &incidents::Model,
+) -> Result<Option<(ContactTier, Vec<secondary_owners::Model>)>, Box<dyn Error + Send + Sync>> {
+    let mut contacts = select_emergency_contacts(ChatId(incident.chat_id));
+    if let Some(tier) = incident.notified_tier {
+        contacts = contacts.filter(secondary_owners::Column::Tier.gt(tier));
+    }
+    let contacts = contacts.all(connection).await?;
+
+    let tier = match contacts.first() {
+        Some(contact) => contact.tier,
+        None => return Ok(None),
+    };
+    let contacts = contacts.into_iter().filter(|x| x.tier == tier).collect();
+    Ok(Some((tier, contacts)))
+}
+
+pub async fn set_notified_tier<C: ConnectionTrait>(
+    connection: &C,
+    incident_id: i32,
+    tier: ContactTier,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    incidents::Entity::update_many()
+        .set(incidents::ActiveModel {
+            notified_tier: ActiveValue::Set(Some(tier)),
+            notified_at: ActiveValue::Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        })
+        .filter(incidents::Column::Id.eq(incident_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Open incidents nobody took on, where the last notified tier had its time to react
+pub fn select_incidents_to_escalate(notified_before: NaiveDateTime) -> Select<incidents::Entity> {
+    incidents::Entity::find()
+        .filter(incidents::Column::ResolvedAt.is_null())
+        .filter(incidents::Column::AcknowledgedBy.is_null())
+        .filter(incidents::Column::NotifiedAt.lte(notified_before))
+        .filter(incidents::Column::NotifiedTier.lt(ContactTier::Tertiary))
+        .order_by_asc(incidents::Column::CreatedAt)
+}
@@ -0,0 +1,276 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use image::{DynamicImage, ImageOutputFormat, Luma};
+use qrcode::QrCode;
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::prelude::*;
+use sea_orm::{sea_query::OnConflict, ActiveValue, QuerySelect, TransactionTrait};
+use serde::Serialize;
+use std::error::Error;
+use std::io::Cursor;
+use teloxide::prelude::*;
+
+use crate::entity::{contact_requests, invites, secondary_owners};
+
+pub const INVITE_CODE_LENGTH: usize = 8;
+pub const INVITE_TTL_HOURS: i64 = 48;
+pub const INVITE_MAX_USES: i32 = 1;
+// Uses are spent on approval, this keeps a leaked code from flooding the owner
+pub const INVITE_MAX_PENDING_REQUESTS: u64 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InviteStatus {
+    Active,
+    Expired,
+    Used,
+    Revoked,
+}
+
+// Revocation wins over the other reasons, it's what the owner did last
+pub fn get_invite_status(invite: &invites::Model, now: NaiveDateTime) -> InviteStatus {
+    if invite.revoked {
+        InviteStatus::Revoked
+    } else if invite.uses >= invite.max_uses {
+        InviteStatus::Used
+    } else if invite.expires_at <= now {
+        InviteStatus::Expired
+    } else {
+        InviteStatus::Active
+    }
+}
+
+fn generate_invite_code() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(INVITE_CODE_LENGTH)
+        .map(char::from)
+        .collect::<String>()
+}
+
+// Opening the link sends `/start <code>` to the bot
+pub fn get_invite_link(bot_username: &str, invite_code: &str) -> String {
+    format!("https://t.me/{}?start={}", bot_username, invite_code)
+}
+
+// PNG with the link as a QR code, for showing the invite from another screen
+pub fn render_qr_code(link: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let image = QrCode::new(link.as_bytes())?
+        .render::<Luma<u8>>()
+        .min_dimensions(512, 512)
+        .build();
+
+    let mut png = vec![];
+    DynamicImage::ImageLuma8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    Ok(png)
+}
+
+pub fn select_invite(chat_id: ChatId) -> Select<invites::Entity> {
+    invites::Entity::find().filter(invites::Column::ChatId.eq(chat_id.0))
+}
+
+pub fn select_invite_by_code(invite_code: &str) -> Select<invites::Entity> {
+    invites::Entity::find().filter(invites::Column::Invite.eq(invite_code))
+}
+
+pub async fn get_or_create_invite(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+) -> Result<invites::Model, Box<dyn Error + Send + Sync>> {
+    match select_invite(chat_id).one(connection).await? {
+        Some(invite) => Ok(invite),
+        None => regenerate_invite(connection, chat_id).await,
+    }
+}
+
+// Replaces the owner's code with a fresh one, the old code stops working
+pub async fn regenerate_invite(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+) -> Result<invites::Model, Box<dyn Error + Send + Sync>> {
+    invites::Entity::insert(invites::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        invite: ActiveValue::Set(generate_invite_code()),
+        expires_at: ActiveValue::Set(Utc::now().naive_utc() + Duration::hours(INVITE_TTL_HOURS)),
+        max_uses: ActiveValue::Set(INVITE_MAX_USES),
+        uses: ActiveValue::Set(0),
+        revoked: ActiveValue::Set(false),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(invites::Column::ChatId)
+            .update_columns([
+                invites::Column::Invite,
+                invites::Column::ExpiresAt,
+                invites::Column::MaxUses,
+                invites::Column::Uses,
+                invites::Column::Revoked,
+            ])
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    match select_invite(chat_id).one(connection).await? {
+        Some(invite) => Ok(invite),
+        None => Err("Invite is missing after regeneration".into()),
+    }
+}
+
+pub async fn revoke_invite(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    invites::Entity::update_many()
+        .col_expr(invites::Column::Revoked, Expr::value(true))
+        .filter(invites::Column::ChatId.eq(chat_id.0))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Counts a use only if the code is still current and not used up at the moment
+// of the update, so two approvals racing for a single-use code can't both pass.
+// Expiry isn't checked, the request came in while the code was valid.
+async fn use_invite<C: ConnectionTrait>(
+    connection: &C,
+    primary_owner_chat_id: ChatId,
+    invite_code: &str,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let result = invites::Entity::update_many()
+        .col_expr(
+            invites::Column::Uses,
+            Expr::col(invites::Column::Uses).add(1),
+        )
+        .filter(invites::Column::ChatId.eq(primary_owner_chat_id.0))
+        .filter(invites::Column::Invite.eq(invite_code))
+        .filter(invites::Column::Revoked.eq(false))
+        .filter(Expr::col(invites::Column::Uses).lt(Expr::col(invites::Column::MaxUses)))
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+// Locks the invite row, so concurrent requests through the same code are
+// counted one after another
+pub async fn lock_invite<C: ConnectionTrait>(
+    connection: &C,
+    invite: &invites::Model,
+) -> Result<Option<invites::Model>, Box<dyn Error + Send + Sync>> {
+    Ok(invites::Entity::find_by_id(invite.id)
+        .filter(invites::Column::Invite.eq(invite.invite.clone()))
+        .lock_exclusive()
+        .one(connection)
+        .await?)
+}
+
+// Requests from other contacts still waiting for the owner with this code
+pub async fn count_pending_contact_requests<C: ConnectionTrait>(
+    connection: &C,
+    invite: &invites::Model,
+    secondary_owner_chat_id: ChatId,
+) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    Ok(contact_requests::Entity::find()
+        .filter(contact_requests::Column::PrimaryOwnerChatId.eq(invite.chat_id))
+        .filter(contact_requests::Column::Invite.eq(invite.invite.clone()))
+        .filter(contact_requests::Column::SecondaryOwnerChatId.ne(secondary_owner_chat_id.0))
+        .count(connection)
+        .await?)
+}
+
+// A repeated request from the same contact replaces the previous one
+pub async fn create_contact_request<C: ConnectionTrait>(
+    connection: &C,
+    invite: &invites::Model,
+    secondary_owner_chat_id: ChatId,
+) -> Result<contact_requests::Model, Box<dyn Error + Send + Sync>> {
+    contact_requests::Entity::delete_many()
+        .filter(contact_requests::Column::PrimaryOwnerChatId.eq(invite.chat_id))
+        .filter(contact_requests::Column::SecondaryOwnerChatId.eq(secondary_owner_chat_id.0))
+        .exec(connection)
+        .await?;
+
+    let request = contact_requests::ActiveModel {
+        primary_owner_chat_id: ActiveValue::Set(invite.chat_id),
+        secondary_owner_chat_id: ActiveValue::Set(secondary_owner_chat_id.0),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        invite: ActiveValue::Set(Some(invite.invite.clone())),
+        ..Default::default()
+    };
+    Ok(request.insert(connection).await?)
+}
+
+// Scoped to the owner, so nobody else can approve a request by its id
+pub fn select_contact_request(
+    primary_owner_chat_id: ChatId,
+    request_id: i32,
+) -> Select<contact_requests::Entity> {
+    contact_requests::Entity::find()
+        .filter(contact_requests::Column::PrimaryOwnerChatId.eq(primary_owner_chat_id.0))
+        .filter(contact_requests::Column::Id.eq(request_id))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactRequestApproval {
+    Approved,
+    // E.g. by a double tap
+    AlreadyHandled,
+    // The code was used up, revoked or replaced since the request came in
+    InviteUnavailable,
+}
+
+pub async fn approve_contact_request<C: TransactionTrait>(
+    connection: &C,
+    request: &contact_requests::Model,
+) -> Result<ContactRequestApproval, Box<dyn Error + Send + Sync>> {
+    let transaction = connection.begin().await?;
+
+    let result = contact_requests::Entity::delete_by_id(request.id)
+        .exec(&transaction)
+        .await?;
+    if result.rows_affected == 0 {
+        return Ok(ContactRequestApproval::AlreadyHandled);
+    }
+    let linked = secondary_owners::Entity::insert(secondary_owners::ActiveModel {
+        primary_owner_chat_id: ActiveValue::Set(request.primary_owner_chat_id),
+        secondary_owner_chat_id: ActiveValue::Set(Some(request.secondary_owner_chat_id)),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    // Already linked, e.g. approved an older request in the meantime
+    .on_conflict(
+        OnConflict::columns([
+            secondary_owners::Column::PrimaryOwnerChatId,
+            secondary_owners::Column::SecondaryOwnerChatId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(&transaction)
+    .await?;
+
+    // The use is spent only by a new link, the transaction is rolled back
+    // together with the deleted request if the code can't take it
+    if let (1, Some(invite_code)) = (linked, &request.invite) {
+        let owner_chat_id = ChatId(request.primary_owner_chat_id);
+        if !use_invite(&transaction, owner_chat_id, invite_code).await? {
+            return Ok(ContactRequestApproval::InviteUnavailable);
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(ContactRequestApproval::Approved)
+}
+
+// Returns `false` if the request was already handled
+pub async fn reject_contact_request<C: ConnectionTrait>(
+    connection: &C,
+    request: &contact_requests::Model,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let result = contact_requests::Entity::delete_by_id(request.id)
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
@@ -0,0 +1,89 @@
+use chrono::Duration;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use std::error::Error;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
+use tera::Tera;
+
+use crate::{
+    config::Config,
+    entity::profiles::{self, Language},
+    health::utils::{get_health_problems, get_last_nudge, mark_nudged, select_monitored_profiles},
+    i18n::{render, Text},
+    outbox::utils::enqueue_message,
+    profiles::utils::get_time_zone,
+    settings::utils::{get_settings, is_check_in_window_open},
+};
+
+fn get_nudge_keyboard(language: Language) -> InlineKeyboardMarkup {
+    let keyboard = vec![vec![InlineKeyboardButton::callback(
+        Text::OwnerMenuButton.get(language),
+        "/owner_menu",
+    )]];
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+async fn nudge(
+    connection: &DatabaseConnection,
+    config: &Config,
+    profile: &profiles::Model,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = ChatId(profile.chat_id);
+    let problems = get_health_problems(connection, chat_id).await?;
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let nudged_before = now - Duration::hours(config.health_nudge_interval_hours);
+    if get_last_nudge(connection, chat_id)
+        .await?
+        .is_some_and(|x| x > nudged_before)
+    {
+        return Ok(());
+    }
+    // Not urgent, so only when the owner would answer a check-in prompt too
+    let settings = get_settings(connection, chat_id).await;
+    if !is_check_in_window_open(&settings, get_time_zone(profile), now) {
+        return Ok(());
+    }
+
+    log::info!("Nudging {:?} about {:?}", profile, problems);
+    let mut context = tera::Context::new();
+    context.insert("problems", &problems);
+    let message = render(tera, profile.language, "health_nudge.html", &context).unwrap();
+
+    let transaction = connection.begin().await?;
+    mark_nudged(&transaction, chat_id).await?;
+    enqueue_message(
+        &transaction,
+        chat_id,
+        message,
+        Some(ParseMode::Html),
+        Some(get_nudge_keyboard(profile.language)),
+    )
+    .await?;
+    transaction.commit().await?;
+    Ok(())
+}
+
+// Finds owners who think they're monitored while an alert couldn't help them
+pub async fn run(
+    connection: &DatabaseConnection,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    log::info!("Checking monitoring health...");
+    let profiles = select_monitored_profiles().all(connection).await?;
+
+    for profile in profiles {
+        let result = nudge(connection, config, &profile, tera).await;
+        if result.is_err() {
+            log::error!("Got error: {:?}", result);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use sea_orm::prelude::*;
+use sea_orm::{EntityTrait, JoinType, QuerySelect};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::{
+    entity::{alive_events, profiles, prompt_events, settings, statuses},
+    i18n::Text,
+    modules::alive::get_alive_keyboard,
+    outbox::utils::{enqueue_check_in_prompt, has_pending_check_in_prompt},
+    profiles::utils::{get_time_zone, select_active_profiles},
+    settings::utils::{get_settings, is_check_in_overdue, is_check_in_window_open},
+};
+
+async fn prompt(
+    connection: &DatabaseConnection,
+    profile: &profiles::Model,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = ChatId(profile.chat_id);
+    let settings = get_settings(connection, chat_id).await;
+    let now = chrono::Utc::now().naive_utc();
+    if !is_check_in_window_open(&settings, get_time_zone(profile), now) {
+        log::info!("Check-in window is closed for {:?}", profile);
+        return Ok(());
+    }
+
+    if has_pending_check_in_prompt(connection, chat_id).await? {
+        log::info!("Check-in prompt is still queued for {:?}", profile);
+        return Ok(());
+    }
+
+    log::info!("Notifying {:?}", profile);
+    enqueue_check_in_prompt(
+        connection,
+        chat_id,
+        Text::CheckInPrompt.get(profile.language).to_string(),
+        get_alive_keyboard(profile.language),
+    )
+    .await
+}
+
+pub async fn run(connection: &DatabaseConnection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    log::info!("Checking statuses...");
+    let profiles = select_active_profiles()
+        .join_rev(
+            JoinType::LeftJoin,
+            alive_events::Entity::belongs_to(statuses::Entity)
+                .from(alive_events::Column::ChatId)
+                .to(statuses::Column::ChatId)
+                .into(),
+        )
+        .join_rev(
+            JoinType::LeftJoin,
+            settings::Entity::belongs_to(profiles::Entity)
+                .from(settings::Column::ChatId)
+                .to(profiles::Column::ChatId)
+                .into(),
+        )
+        .join_rev(
+            JoinType::LeftJoin,
+            prompt_events::Entity::belongs_to(profiles::Entity)
+                .from(prompt_events::Column::ChatId)
+                .to(profiles::Column::ChatId)
+                .into(),
+        )
+        .filter(is_check_in_overdue().or(alive_events::Column::Timestamp.is_null()))
+        // Prompt only once per missed check-in
+        .filter(prompt_events::Column::Timestamp.is_null().or(
+            Expr::col((prompt_events::Entity, prompt_events::Column::Timestamp)).lt(Expr::col((
+                alive_events::Entity,
+                alive_events::Column::Timestamp,
+            ))),
+        ))
+        .all(connection)
+        .await?;
+
+    for profile in profiles {
+        // One owner's failure shouldn't leave the rest unprompted
+        let result = prompt(connection, &profile).await;
+        if result.is_err() {
+            log::error!("Got error: {:?}", result);
+        }
+    }
+
+    Ok(())
+}
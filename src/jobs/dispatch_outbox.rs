@@ -0,0 +1,196 @@
+use chrono::Utc;
+use sea_orm::{DatabaseConnection, EntityTrait, TransactionTrait};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::MessageId;
+use teloxide::RequestError;
+use tokio::time::Instant;
+
+use crate::{
+    entity::{outbox_messages, secondary_owners},
+    modules::{alive::mark_prompted, contacts::handle_unreachable_chat},
+    notifiers::{telegram::get_notify_error, Notification, Notifiers, NotifyError},
+    outbox::utils::{
+        claim_message, get_payload, mark_message_sent, postpone_message, record_message_error,
+        select_due_messages, OutboxPayload,
+    },
+};
+
+// Telegram allows about 30 messages a second overall and one a second per chat
+pub const GLOBAL_INTERVAL: Duration = Duration::from_millis(34);
+pub const CHAT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Lives as long as the dispatcher, so the limits hold across runs
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    sent_at: Option<Instant>,
+    chats: HashMap<i64, Instant>,
+}
+
+impl RateLimiter {
+    pub fn is_ready(&self, chat_id: i64, now: Instant) -> bool {
+        self.chats.get(&chat_id).map_or(true, |sent_at| {
+            now.duration_since(*sent_at) >= CHAT_INTERVAL
+        })
+    }
+
+    // Waits for the global limit and takes the slot for the chat, if there's one
+    pub async fn acquire(&mut self, chat_id: Option<i64>) {
+        if let Some(sent_at) = self.sent_at {
+            tokio::time::sleep_until(sent_at + GLOBAL_INTERVAL).await;
+        }
+
+        let now = Instant::now();
+        self.sent_at = Some(now);
+        self.chats
+            .retain(|_, sent_at| now.duration_since(*sent_at) < CHAT_INTERVAL);
+        if let Some(chat_id) = chat_id {
+            self.chats.insert(chat_id, now);
+        }
+    }
+}
+
+enum SendError {
+    RetryAfter(Duration),
+    Failed(NotifyError),
+}
+
+impl From<RequestError> for SendError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::RetryAfter(duration) => SendError::RetryAfter(duration),
+            err => SendError::Failed(get_notify_error(&err)),
+        }
+    }
+}
+
+async fn send(
+    bot: &Bot,
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    message: &outbox_messages::Model,
+) -> Result<(), SendError> {
+    let payload = get_payload(message)
+        .map_err(|err| SendError::Failed(NotifyError::Permanent(err.to_string())))?;
+    let chat_id = message.chat_id.map(ChatId);
+
+    match (payload, chat_id) {
+        (
+            OutboxPayload::Message {
+                text,
+                parse_mode,
+                keyboard,
+            },
+            Some(chat_id),
+        ) => {
+            let mut request = bot.send_message(chat_id, text);
+            if let Some(parse_mode) = parse_mode {
+                request = request.parse_mode(parse_mode);
+            }
+            if let Some(keyboard) = keyboard {
+                request = request.reply_markup(keyboard);
+            }
+            request.await?;
+        }
+        (OutboxPayload::DeleteMessage { message_id }, Some(chat_id)) => {
+            bot.delete_message(chat_id, MessageId(message_id)).await?;
+        }
+        (
+            OutboxPayload::Notice {
+                subject,
+                html,
+                keyboard,
+            },
+            _,
+        ) => {
+            let link = match message.link_id {
+                Some(link_id) => secondary_owners::Entity::find_by_id(link_id)
+                    .one(connection)
+                    .await
+                    .map_err(|err| SendError::Failed(NotifyError::Transient(err.to_string())))?,
+                None => None,
+            };
+            let link = match link {
+                Some(link) => link,
+                None => {
+                    let error = NotifyError::Permanent("Contact removed".to_string());
+                    return Err(SendError::Failed(error));
+                }
+            };
+            let notification = Notification {
+                subject,
+                html,
+                keyboard,
+                ..Default::default()
+            };
+            notifiers
+                .notify(&link, &notification)
+                .await
+                .map_err(SendError::Failed)?;
+        }
+        (_, None) => {
+            let error = NotifyError::Permanent("No chat to send to".to_string());
+            return Err(SendError::Failed(error));
+        }
+    }
+    Ok(())
+}
+
+// Sends what's due in the outbox, returns how many messages went out
+pub async fn run(
+    connection: &DatabaseConnection,
+    bot: &Bot,
+    notifiers: &Notifiers,
+    limiter: &mut RateLimiter,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let now = Utc::now().naive_utc();
+    let messages = select_due_messages(now).all(connection).await?;
+
+    let mut sent = 0;
+    // Chats that got a message too recently, the rest of their queue waits
+    let mut busy_chats = HashSet::new();
+    for message in messages {
+        if let Some(chat_id) = message.chat_id {
+            if busy_chats.contains(&chat_id) || !limiter.is_ready(chat_id, Instant::now()) {
+                busy_chats.insert(chat_id);
+                continue;
+            }
+        }
+        if !claim_message(connection, &message, now).await? {
+            continue;
+        }
+
+        limiter.acquire(message.chat_id).await;
+        match send(bot, connection, notifiers, &message).await {
+            Ok(()) => {
+                let transaction = connection.begin().await?;
+                let marked = mark_message_sent(&transaction, message.id).await?;
+                if let (true, true, Some(chat_id)) =
+                    (marked, message.check_in_prompt, message.chat_id)
+                {
+                    mark_prompted(&transaction, ChatId(chat_id)).await?;
+                }
+                transaction.commit().await?;
+                sent += 1;
+            }
+            // Telegram's flood control applies to the whole bot, so stop here
+            Err(SendError::RetryAfter(duration)) => {
+                log::warn!("Rate limited for {:?}", duration);
+                let until = Utc::now().naive_utc() + chrono::Duration::from_std(duration)?;
+                postpone_message(connection, &message, until).await?;
+                break;
+            }
+            Err(SendError::Failed(error)) => {
+                log::error!("Can't send {:?}: {}", message, error);
+                record_message_error(connection, &message, &error).await?;
+                if let (NotifyError::Unreachable(_), Some(chat_id)) = (&error, message.chat_id) {
+                    handle_unreachable_chat(connection, ChatId(chat_id), &error).await?;
+                }
+            }
+        }
+    }
+
+    Ok(sent)
+}
@@ -0,0 +1,5 @@
+pub mod check_health;
+pub mod confirm_alive;
+pub mod dispatch_outbox;
+pub mod scheduler;
+pub mod send_alerts;
@@ -0,0 +1,183 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, SqlxPostgresConnector, Statement};
+use sqlx::postgres::PgPoolOptions;
+use std::error::Error;
+use std::time::Duration;
+use teloxide::prelude::*;
+use tera::Tera;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::notifiers::Notifiers;
+
+use super::dispatch_outbox::{self, RateLimiter};
+use super::{check_health, confirm_alive, send_alerts};
+
+// Arbitrary, but must stay unique across everything sharing the database
+const CONFIRM_ALIVE_LOCK: i64 = 0x7275_7374_0001;
+const SEND_ALERTS_LOCK: i64 = 0x7275_7374_0002;
+const DISPATCH_OUTBOX_LOCK: i64 = 0x7275_7374_0003;
+const CHECK_HEALTH_LOCK: i64 = 0x7275_7374_0004;
+
+// Frequent enough that a reply through the outbox doesn't feel delayed
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Session-level advisory locks belong to the connection that took them, so
+// they're taken on a dedicated one the pool never closes or swaps. The jobs
+// run on the main pool and don't keep a connection busy while they hold a lock.
+pub async fn connect_locks(db_url: &str) -> Result<DatabaseConnection, Box<dyn Error>> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect(db_url)
+        .await?;
+    Ok(SqlxPostgresConnector::from_sqlx_postgres_pool(pool))
+}
+
+async fn query_lock(
+    locks: &DatabaseConnection,
+    sql: &str,
+    key: i64,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let done = locks
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [key.into()],
+        ))
+        .await?
+        .map(|row| row.try_get::<bool>("", "done"))
+        .transpose()?
+        .unwrap_or(false);
+    Ok(done)
+}
+
+// Returns `false` if another replica holds the lock
+async fn try_lock(
+    locks: &DatabaseConnection,
+    key: i64,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    query_lock(locks, "SELECT pg_try_advisory_lock($1) AS done", key).await
+}
+
+// If the connection dropped meanwhile, the server has already released the lock
+async fn unlock(locks: &DatabaseConnection, key: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !query_lock(locks, "SELECT pg_advisory_unlock($1) AS done", key).await? {
+        log::warn!("Lock {:#x} was lost before the job finished", key);
+    }
+    Ok(())
+}
+
+async fn run_confirm_alive(
+    connection: &DatabaseConnection,
+    locks: &DatabaseConnection,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !try_lock(locks, CONFIRM_ALIVE_LOCK).await? {
+        log::info!("Skipping confirm-alive, another replica is running it");
+        return Ok(());
+    }
+    let result = confirm_alive::run(connection).await;
+    unlock(locks, CONFIRM_ALIVE_LOCK).await?;
+    result
+}
+
+async fn run_send_alerts(
+    connection: &DatabaseConnection,
+    locks: &DatabaseConnection,
+    notifiers: &Notifiers,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !try_lock(locks, SEND_ALERTS_LOCK).await? {
+        log::info!("Skipping send-alerts, another replica is running it");
+        return Ok(());
+    }
+    let result = send_alerts::run(connection, notifiers, config, tera).await;
+    unlock(locks, SEND_ALERTS_LOCK).await?;
+    result
+}
+
+async fn run_check_health(
+    connection: &DatabaseConnection,
+    locks: &DatabaseConnection,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !try_lock(locks, CHECK_HEALTH_LOCK).await? {
+        log::info!("Skipping check-health, another replica is running it");
+        return Ok(());
+    }
+    let result = check_health::run(connection, config, tera).await;
+    unlock(locks, CHECK_HEALTH_LOCK).await?;
+    result
+}
+
+pub fn spawn(
+    connection: DatabaseConnection,
+    locks: DatabaseConnection,
+    notifiers: Notifiers,
+    config: Config,
+    tera: Tera,
+) {
+    tokio::spawn(async move {
+        let period = Duration::from_secs(config.scheduler_interval_minutes * 60);
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(error) = run_confirm_alive(&connection, &locks).await {
+                log::error!("confirm-alive failed: {:?}", error);
+            }
+            if let Err(error) =
+                run_send_alerts(&connection, &locks, &notifiers, &config, &tera).await
+            {
+                log::error!("send-alerts failed: {:?}", error);
+            }
+            if let Err(error) = run_check_health(&connection, &locks, &config, &tera).await {
+                log::error!("check-health failed: {:?}", error);
+            }
+        }
+    });
+}
+
+// One replica at a time sends, so the rate limits hold for the whole bot
+async fn run_dispatch_outbox(
+    connection: &DatabaseConnection,
+    locks: &DatabaseConnection,
+    bot: &Bot,
+    notifiers: &Notifiers,
+    limiter: &mut RateLimiter,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !try_lock(locks, DISPATCH_OUTBOX_LOCK).await? {
+        return Ok(());
+    }
+    let result = dispatch_outbox::run(connection, bot, notifiers, limiter).await;
+    unlock(locks, DISPATCH_OUTBOX_LOCK).await?;
+    result.map(|_| ())
+}
+
+// Runs regardless of `scheduler_enabled`, the handlers queue messages too
+pub fn spawn_dispatcher(
+    connection: DatabaseConnection,
+    locks: DatabaseConnection,
+    bot: Bot,
+    notifiers: Notifiers,
+) {
+    tokio::spawn(async move {
+        let mut limiter = RateLimiter::default();
+        let mut interval = tokio::time::interval(DISPATCH_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(error) =
+                run_dispatch_outbox(&connection, &locks, &bot, &notifiers, &mut limiter).await
+            {
+                log::error!("dispatch-outbox failed: {:?}", error);
+            }
+        }
+    });
+}
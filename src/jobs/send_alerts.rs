@@ -0,0 +1,427 @@
+use chrono::{Duration, NaiveDateTime};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+use std::error::Error;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
+use tera::Tera;
+
+use crate::{
+    config::Config,
+    crypto::Keyring,
+    deliveries::utils::{
+        claim_delivery, create_deliveries, mark_delivery_sent, record_delivery_error,
+        select_due_deliveries, select_incidents_to_retry,
+    },
+    emergency_info::utils::{get_attachments, get_sections},
+    entity::{
+        alert_deliveries::DeliveryStatus,
+        alive_events,
+        escalations::EscalationStage,
+        incidents, profiles, prompt_events,
+        secondary_owners::{self, ContactChannel},
+    },
+    escalations::utils::{get_due_stage, get_stage, set_stage},
+    i18n::{render, Text},
+    incidents::utils::{
+        get_next_tier, open_incident, select_incidents_to_escalate, set_notified_tier,
+    },
+    modules::{
+        alive::get_alive_keyboard, contacts::handle_unreachable_chat,
+        emergency_info::format_sections, incidents::get_incident_keyboard,
+    },
+    notifiers::{Notification, Notifiers, NotifyError},
+    outbox::utils::{enqueue_message, enqueue_notice},
+    pets::utils::get_pets,
+    profiles::utils::{
+        get_contact_language, get_contact_name, get_display_name, get_language, get_time_zone,
+        select_active_profiles, select_emergency_contacts,
+    },
+    settings::utils::{get_settings, is_prompt_pending, is_quiet_time},
+    statuses::utils::set_monitoring,
+};
+
+async fn send_reminder<C: ConnectionTrait>(
+    connection: &C,
+    profile: &profiles::Model,
+    tera: &Tera,
+    loud: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut context = tera::Context::new();
+    context.insert("loud", &loud);
+    let message = render(tera, profile.language, "alert_reminder.html", &context).unwrap();
+    enqueue_message(
+        connection,
+        ChatId(profile.chat_id),
+        message,
+        Some(ParseMode::Html),
+        Some(get_alive_keyboard(profile.language)),
+    )
+    .await
+}
+
+async fn send_heads_up<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    tera: &Tera,
+    silence_hours: i64,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let recipient = match select_emergency_contacts(chat_id).one(connection).await? {
+        Some(recipient) => recipient,
+        None => return Ok(()),
+    };
+
+    let language = get_contact_language(connection, &recipient).await;
+    let username = get_display_name(connection, chat_id, language).await;
+    let mut context = tera::Context::new();
+    context.insert("username", &username);
+    context.insert("silence_hours", &silence_hours);
+    let message = render(tera, language, "alert_heads_up.html", &context).unwrap();
+
+    log::info!("Notifying {:?}", recipient);
+    let notification = Notification {
+        subject: Text::NotificationSubject.format(language, &[("username", &username)]),
+        html: message,
+        ..Default::default()
+    };
+    enqueue_notice(connection, &recipient, &notification).await
+}
+
+async fn get_silence_hours(
+    connection: &DatabaseConnection,
+    incident: &incidents::Model,
+    now: NaiveDateTime,
+) -> Result<i64, Box<dyn Error + Send + Sync>> {
+    let last_seen_at = alive_events::Entity::find()
+        .filter(alive_events::Column::ChatId.eq(incident.chat_id))
+        .one(connection)
+        .await?
+        .map_or(incident.created_at, |x| x.timestamp);
+    Ok((now - last_seen_at).num_hours())
+}
+
+// Tells the owner who didn't get the alert, so they can fix it once they're back
+async fn report_unreachable(
+    connection: &DatabaseConnection,
+    incident: &incidents::Model,
+    links: &[secondary_owners::Model],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = ChatId(incident.chat_id);
+    let language = get_language(connection, chat_id).await;
+    let mut contacts = vec![];
+    for link in links {
+        contacts.push(get_contact_name(connection, link, language).await);
+    }
+
+    let message = Text::ContactsUnreachable.format(language, &[("contacts", &contacts.join(", "))]);
+    enqueue_message(connection, chat_id, message, None, None).await
+}
+
+// Sends the incident's alerts that are due, failed ones are retried on later runs
+async fn deliver_alerts(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    keyring: &Keyring,
+    incident: &incidents::Model,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let now = chrono::Utc::now().naive_utc();
+    let deliveries = select_due_deliveries(incident.id, now)
+        .all(connection)
+        .await?;
+    if deliveries.is_empty() {
+        return Ok(());
+    }
+
+    let chat_id = ChatId(incident.chat_id);
+    let silence_hours = get_silence_hours(connection, incident, now).await?;
+    let sections = get_sections(connection, keyring, chat_id).await?;
+    let attachments = get_attachments(connection, keyring, chat_id).await?;
+    let pets = get_pets(connection, keyring, chat_id).await?;
+
+    let mut unreachable = vec![];
+    for delivery in deliveries {
+        if !claim_delivery(connection, &delivery, now).await? {
+            continue;
+        }
+        let recipient = match secondary_owners::Entity::find_by_id(delivery.link_id)
+            .one(connection)
+            .await?
+        {
+            Some(recipient) => recipient,
+            None => {
+                let error = NotifyError::Permanent("Contact removed".to_string());
+                record_delivery_error(connection, &delivery, &error).await?;
+                continue;
+            }
+        };
+
+        // Attachments and photos only reach contacts on Telegram
+        let is_chat = recipient.channel == ContactChannel::Telegram;
+        let language = get_contact_language(connection, &recipient).await;
+        let username = get_display_name(connection, chat_id, language).await;
+        let mut context = tera::Context::new();
+        context.insert("username", &username);
+        context.insert("silence_hours", &silence_hours);
+        context.insert("sections", &format_sections(&sections, language));
+        context.insert(
+            "attachments_count",
+            &if is_chat { attachments.len() } else { 0 },
+        );
+        context.insert("pets", &pets);
+        let message = render(tera, language, "alert_contact.html", &context).unwrap();
+
+        log::info!(
+            "Notifying {:?}, attempt {}",
+            recipient,
+            delivery.attempts + 1
+        );
+        let notification = Notification {
+            subject: Text::NotificationSubject.format(language, &[("username", &username)]),
+            html: message,
+            keyboard: Some(get_incident_keyboard(incident.id, language)),
+            attachments: attachments.clone(),
+            photos: pets
+                .iter()
+                .filter_map(|pet| {
+                    let photo_file_id = pet.photo_file_id.clone()?;
+                    Some((photo_file_id, format!("🐾 {}", pet.name)))
+                })
+                .collect(),
+        };
+        match notifiers.notify(&recipient, &notification).await {
+            Ok(()) => mark_delivery_sent(connection, delivery.id).await?,
+            Err(error) => {
+                log::error!("Can't notify {:?}: {}", recipient, error);
+                let status = record_delivery_error(connection, &delivery, &error).await?;
+                if let (NotifyError::Unreachable(_), Some(chat_id)) =
+                    (&error, recipient.secondary_owner_chat_id)
+                {
+                    handle_unreachable_chat(connection, ChatId(chat_id), &error).await?;
+                }
+                if status == DeliveryStatus::Failed {
+                    unreachable.push(recipient);
+                }
+            }
+        }
+    }
+
+    if !unreachable.is_empty() {
+        report_unreachable(connection, incident, &unreachable).await?;
+    }
+    Ok(())
+}
+
+// Picks the next tier of contacts that hasn't got this incident yet and
+// records their deliveries, returns `false` if there's nobody left
+async fn schedule_next_tier<C: ConnectionTrait>(
+    connection: &C,
+    incident: &incidents::Model,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let (tier, recipients) = match get_next_tier(connection, incident).await? {
+        Some(next) => next,
+        None => return Ok(false),
+    };
+    log::info!("Alerting {:?} contacts for {:?}", tier, incident);
+    create_deliveries(connection, incident.id, &recipients).await?;
+    set_notified_tier(connection, incident.id, tier).await?;
+    Ok(true)
+}
+
+// The deliveries are committed before anything is sent, so a crash in
+// between leaves them for `retry_alerts` and the alerts still go out once
+async fn notify_next_tier(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    keyring: &Keyring,
+    incident: &incidents::Model,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let transaction = connection.begin().await?;
+    let scheduled = schedule_next_tier(&transaction, incident).await?;
+    transaction.commit().await?;
+
+    if !scheduled {
+        return Ok(());
+    }
+    deliver_alerts(connection, notifiers, keyring, incident, tera).await
+}
+
+async fn send_alert<C: ConnectionTrait>(
+    connection: &C,
+    profile: &profiles::Model,
+    tera: &Tera,
+) -> Result<incidents::Model, Box<dyn Error + Send + Sync>> {
+    let chat_id = ChatId(profile.chat_id);
+    set_monitoring(connection, chat_id, false).await?;
+    let incident = open_incident(connection, chat_id).await?;
+
+    let context = tera::Context::new();
+    let message = render(tera, profile.language, "alert_owner.html", &context).unwrap();
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        Text::OwnerMenuButton.get(profile.language),
+        "/owner_menu",
+    )]]);
+    enqueue_message(connection, chat_id, message, None, Some(keyboard)).await?;
+
+    schedule_next_tier(connection, &incident).await?;
+    Ok(incident)
+}
+
+// Moves unacknowledged incidents on to the next tier once the wait is over
+async fn escalate_incidents(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let now = chrono::Utc::now().naive_utc();
+    let notified_before = now - Duration::minutes(config.alert_tier_wait_minutes);
+    let incidents = select_incidents_to_escalate(notified_before)
+        .all(connection)
+        .await?;
+
+    for incident in incidents {
+        let result =
+            notify_next_tier(connection, notifiers, &config.keyring, &incident, tera).await;
+        if result.is_err() {
+            log::error!("Got error: {:?}", result);
+        }
+    }
+    Ok(())
+}
+
+// Gives alerts that failed on earlier runs another try
+async fn retry_alerts(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let now = chrono::Utc::now().naive_utc();
+    let incidents = select_incidents_to_retry(now).all(connection).await?;
+
+    for incident in incidents {
+        let result = deliver_alerts(connection, notifiers, &config.keyring, &incident, tera).await;
+        if result.is_err() {
+            log::error!("Got error: {:?}", result);
+        }
+    }
+    Ok(())
+}
+
+async fn escalate(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    config: &Config,
+    profile: &profiles::Model,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = ChatId(profile.chat_id);
+    let now = chrono::Utc::now().naive_utc();
+
+    let prompted_at = match prompt_events::Entity::find()
+        .filter(prompt_events::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await?
+    {
+        Some(prompt) => prompt.timestamp,
+        None => return Ok(()),
+    };
+    let last_seen_at = alive_events::Entity::find()
+        .filter(alive_events::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await?
+        .map_or(prompted_at, |x| x.timestamp);
+    let silence_hours = (now - last_seen_at).num_hours();
+
+    let settings = get_settings(connection, chat_id).await;
+    let current_stage = get_stage(connection, chat_id).await;
+    let due_stage = get_due_stage(config, now - prompted_at, settings.alert_grace_hours);
+    if due_stage <= current_stage {
+        return Ok(());
+    }
+
+    let is_reminder = matches!(
+        due_stage,
+        EscalationStage::Reminder | EscalationStage::SecondReminder
+    );
+    if is_reminder && is_quiet_time(&settings, get_time_zone(profile), now) {
+        log::info!(
+            "Postponing reminder for {:?} until quiet hours end",
+            profile
+        );
+        return Ok(());
+    }
+
+    // The stage and its messages are committed together, so a crash neither
+    // skips a stage nor sends it twice
+    log::info!("Escalating {:?} to {:?}", profile, due_stage);
+    let transaction = connection.begin().await?;
+    set_stage(&transaction, chat_id, due_stage).await?;
+    let incident = match due_stage {
+        EscalationStage::None => None,
+        EscalationStage::Reminder => {
+            send_reminder(&transaction, profile, tera, false).await?;
+            None
+        }
+        EscalationStage::SecondReminder => {
+            send_reminder(&transaction, profile, tera, true).await?;
+            None
+        }
+        EscalationStage::HeadsUp => {
+            send_heads_up(&transaction, chat_id, tera, silence_hours).await?;
+            None
+        }
+        EscalationStage::Alert => Some(send_alert(&transaction, profile, tera).await?),
+    };
+    transaction.commit().await?;
+
+    match incident {
+        Some(incident) => {
+            deliver_alerts(connection, notifiers, &config.keyring, &incident, tera).await
+        }
+        None => Ok(()),
+    }
+}
+
+pub async fn run(
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    config: &Config,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    log::info!("Checking statuses...");
+
+    let profiles = select_active_profiles()
+        .join_rev(
+            JoinType::LeftJoin,
+            alive_events::Entity::belongs_to(profiles::Entity)
+                .from(alive_events::Column::ChatId)
+                .to(profiles::Column::ChatId)
+                .into(),
+        )
+        .join_rev(
+            JoinType::InnerJoin,
+            prompt_events::Entity::belongs_to(profiles::Entity)
+                .from(prompt_events::Column::ChatId)
+                .to(profiles::Column::ChatId)
+                .into(),
+        )
+        // Count only time since the last delivered prompt
+        .filter(is_prompt_pending())
+        .all(connection)
+        .await?;
+
+    for profile in profiles {
+        let result = escalate(connection, notifiers, config, &profile, tera).await;
+        if result.is_err() {
+            log::error!("Got error: {:?}", result);
+        }
+    }
+
+    escalate_incidents(connection, notifiers, config, tera).await?;
+    retry_alerts(connection, notifiers, config, tera).await
+}
@@ -1,8 +1,23 @@
 pub mod config;
 pub mod connection;
+pub mod crypto;
+pub mod deliveries;
+pub mod emergency_info;
 pub mod entity;
+pub mod escalations;
+pub mod health;
+pub mod i18n;
+pub mod incidents;
+pub mod invites;
+pub mod jobs;
 pub mod migration;
 pub mod modules;
+pub mod notifiers;
+pub mod outbox;
+pub mod pets;
 pub mod profiles;
+pub mod settings;
 pub mod statuses;
+pub mod storage;
+pub mod transport;
 pub mod types;
@@ -1,34 +1,61 @@
 use sea_orm::sea_query::OnConflict;
-use sea_orm::{ActiveValue, DatabaseConnection, EntityTrait};
+use sea_orm::{ActiveValue, DatabaseConnection, EntityTrait, TransactionTrait};
 use std::error::Error;
-use teloxide::dispatching::dialogue::{GetChatId, InMemStorage};
+use teloxide::dispatching::dialogue::GetChatId;
 use teloxide::prelude::*;
+use teloxide::types::Me;
 use teloxide::utils::command::BotCommands;
 use tera::Tera;
 use trusty_tail::config::Config;
-use trusty_tail::modules::alive::{mark_alive, mark_alive_callback};
+use trusty_tail::crypto::Keyring;
+use trusty_tail::emergency_info::utils::parse_section;
+use trusty_tail::i18n::Text;
+use trusty_tail::jobs::scheduler;
+use trusty_tail::modules::alive::{check_in, mark_alive_callback};
 use trusty_tail::modules::contact_menu::show_contact_menu;
+use trusty_tail::modules::contacts::{
+    ask_for_external_contact, ask_to_leave_owner, handle_add_external_contact, handle_leave_owner,
+    handle_remove_emergency_contact, handle_set_contact_tier, show_emergency_contact,
+};
 use trusty_tail::modules::emergency_info::{
-    ask_for_emergency_info, set_emergency_info, show_emergency_info,
+    ask_for_attachment, ask_for_emergency_info, ask_for_emergency_section, handle_add_attachment,
+    handle_remove_attachment, handle_set_emergency_section, show_emergency_info,
 };
-use trusty_tail::modules::invites::{accept_invite, ask_for_invite};
+use trusty_tail::modules::incidents::handle_acknowledge_incident;
+use trusty_tail::modules::invites::{accept_invite, ask_for_invite, handle_contact_request};
+use trusty_tail::modules::language::{handle_set_language, show_language_menu};
 use trusty_tail::modules::owner_menu::{
-    handle_disable_monitoring, handle_enable_monitoring, show_owner_menu,
+    handle_disable_monitoring, handle_enable_monitoring, handle_regenerate_invite,
+    handle_revoke_invite, send_invite_qr_code, show_owner_menu,
+};
+use trusty_tail::modules::pets::{
+    ask_for_pet_field, ask_for_pet_name, ask_to_remove_pet, handle_add_pet, handle_remove_pet,
+    handle_set_pet_field, show_pet, show_pets,
+};
+use trusty_tail::modules::settings::{
+    ask_for_time_zone, handle_set_alert_grace, handle_set_check_in_interval,
+    handle_set_check_in_window, handle_set_quiet_hours, handle_set_time_zone,
+    show_monitoring_settings,
 };
 use trusty_tail::modules::start::show_start_info;
+use trusty_tail::notifiers::Notifiers;
+use trusty_tail::pets::utils::parse_pet_field;
+use trusty_tail::profiles::utils::{get_language, parse_language};
+use trusty_tail::storage::DatabaseStorage;
+use trusty_tail::transport;
 use trusty_tail::types::{BotDialogState, BotDialogue};
 use trusty_tail::{connection, entity::*};
 
 #[derive(BotCommands, Clone, PartialEq, Eq)]
 #[command(rename_rule = "snake_case")]
 enum MessageCommand {
-    Start,
+    // Optional deep link payload
+    Start(String),
     // Legacy
     Menu,
     OwnerMenu,
     ContactMenu,
-    Enable,
-    Disable,
+    Language,
 }
 
 #[derive(BotCommands, Clone, PartialEq, Eq)]
@@ -40,6 +67,41 @@ enum CallbackCommand {
     ContactMenu,
     AskForInvite,
     MarkAlive,
+    EnableMonitoring,
+    DisableMonitoring,
+    MonitoringSettings,
+    SetCheckInInterval(i32),
+    SetAlertGrace(i32),
+    #[command(parse_with = "split")]
+    SetCheckInWindow(i32, i32),
+    #[command(parse_with = "split")]
+    SetQuietHours(i32, i32),
+    AskForTimeZone,
+    AcknowledgeIncident(i32),
+    SetLanguage(String),
+    Pets,
+    Pet(i32),
+    AddPet,
+    #[command(parse_with = "split")]
+    EditPet(i32, String),
+    RemovePet(i32),
+    ConfirmRemovePet(i32),
+    EmergencyWizard(i32),
+    EditEmergencySection(i32),
+    AskForAttachment,
+    RemoveAttachment(i32),
+    RegenerateInvite,
+    RevokeInvite,
+    InviteQrCode,
+    ApproveContact(i32),
+    RejectContact(i32),
+    Contact(i32),
+    AskForExternalContact,
+    #[command(parse_with = "split")]
+    SetContactTier(i32, i32),
+    RemoveContact(i32),
+    LeaveOwner(i32),
+    ConfirmLeaveOwner(i32),
 }
 
 async fn update_profile_middleware(message: Message, connection: DatabaseConnection) {
@@ -47,15 +109,24 @@ async fn update_profile_middleware(message: Message, connection: DatabaseConnect
         .from()
         .and_then(|user| user.username.clone())
         .unwrap_or("Unknown".to_string());
+    // Only seeds new profiles, a language picked with /language is kept
+    let language = message
+        .from()
+        .and_then(|user| user.language_code.as_deref())
+        .and_then(parse_language)
+        .unwrap_or_default();
 
     let _ = profiles::Entity::insert(profiles::ActiveModel {
         chat_id: ActiveValue::Set(message.chat.id.0),
         username: ActiveValue::Set(username),
+        language: ActiveValue::Set(language),
+        unreachable_at: ActiveValue::Set(None),
         ..Default::default()
     })
+    // Writing to the bot means it's not blocked anymore
     .on_conflict(
         OnConflict::column(profiles::Column::ChatId)
-            .update_column(profiles::Column::Username)
+            .update_columns([profiles::Column::Username, profiles::Column::UnreachableAt])
             .to_owned(),
     )
     .exec(&connection)
@@ -63,15 +134,33 @@ async fn update_profile_middleware(message: Message, connection: DatabaseConnect
     .unwrap();
 }
 
-async fn mark_alive_middleware(message: Message, connection: DatabaseConnection) {
-    mark_alive(&connection, message.chat.id).await.unwrap();
+async fn check_in_and_commit(
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let transaction = connection.begin().await?;
+    check_in(chat_id, &transaction, tera).await?;
+    transaction.commit().await?;
+    Ok(())
 }
 
+async fn mark_alive_middleware(message: Message, connection: DatabaseConnection, tera: Tera) {
+    if let Err(error) = check_in_and_commit(message.chat.id, &connection, &tera).await {
+        log::error!("Failed to check in: {:?}", error);
+    }
+}
+
+// dptree injects every dependency as an argument
+#[allow(clippy::too_many_arguments)]
 async fn callback_handler(
     bot: Bot,
+    me: Me,
     query: CallbackQuery,
     dialogue: BotDialogue,
     connection: DatabaseConnection,
+    keyring: Keyring,
+    notifiers: Notifiers,
     tera: Tera,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     let chat_id = match query.chat_id() {
@@ -84,33 +173,128 @@ async fn callback_handler(
         None => return Err("No message id".into()),
     };
 
-    let command = match query
-        .data
-        .map(|x| CallbackCommand::parse(&x, "").ok())
-        .flatten()
-    {
+    let command = match query.data.and_then(|x| CallbackCommand::parse(&x, "").ok()) {
         Some(command) => command,
         None => {
-            bot.send_message(chat_id, "Команда не найдена").await?;
-            show_owner_menu(&bot, chat_id, &connection, &tera).await?;
+            let language = get_language(&connection, chat_id).await;
+            bot.send_message(chat_id, Text::CommandNotFound.get(language))
+                .await?;
+            show_owner_menu(&bot, &me, chat_id, &connection, &tera).await?;
             return Err("Unknown command".into());
         }
     };
 
     let next_state = match command {
         CallbackCommand::EmergencyInfo => {
-            show_emergency_info(&bot, chat_id, &connection, &tera).await?
+            show_emergency_info(&bot, chat_id, &connection, &keyring, &tera).await?
         }
         CallbackCommand::AskForEmergencyInfo => {
-            ask_for_emergency_info(&bot, chat_id, &tera).await?
+            ask_for_emergency_info(&bot, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::OwnerMenu => {
+            show_owner_menu(&bot, &me, chat_id, &connection, &tera).await?
         }
-        CallbackCommand::OwnerMenu => show_owner_menu(&bot, chat_id, &connection, &tera).await?,
         CallbackCommand::ContactMenu => {
             show_contact_menu(&bot, chat_id, &connection, &tera).await?
         }
-        CallbackCommand::AskForInvite => ask_for_invite(&bot, chat_id).await?,
+        CallbackCommand::AskForInvite => ask_for_invite(&bot, chat_id, &connection).await?,
         CallbackCommand::MarkAlive => {
-            mark_alive_callback(&bot, chat_id, message_id, &connection).await?
+            mark_alive_callback(chat_id, message_id, &connection, &tera).await?
+        }
+        CallbackCommand::EnableMonitoring => {
+            handle_enable_monitoring(&bot, &me, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::DisableMonitoring => {
+            handle_disable_monitoring(&bot, &me, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::MonitoringSettings => {
+            show_monitoring_settings(&bot, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::SetCheckInInterval(hours) => {
+            handle_set_check_in_interval(&bot, chat_id, hours, &connection, &tera).await?
+        }
+        CallbackCommand::SetAlertGrace(hours) => {
+            handle_set_alert_grace(&bot, chat_id, hours, &connection, &tera).await?
+        }
+        CallbackCommand::SetCheckInWindow(start, end) => {
+            handle_set_check_in_window(&bot, chat_id, (start, end), &connection, &tera).await?
+        }
+        CallbackCommand::SetQuietHours(start, end) => {
+            handle_set_quiet_hours(&bot, chat_id, (start, end), &connection, &tera).await?
+        }
+        CallbackCommand::AskForTimeZone => {
+            ask_for_time_zone(&bot, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::AcknowledgeIncident(incident_id) => {
+            handle_acknowledge_incident(&bot, chat_id, incident_id, &connection, &tera).await?
+        }
+        CallbackCommand::SetLanguage(code) => {
+            handle_set_language(&bot, chat_id, &code, &connection).await?
+        }
+        CallbackCommand::Pets => show_pets(&bot, chat_id, &connection, &tera).await?,
+        CallbackCommand::Pet(pet_id) => {
+            show_pet(&bot, chat_id, pet_id, &connection, &keyring, &tera).await?
+        }
+        CallbackCommand::AddPet => ask_for_pet_name(&bot, chat_id, &connection).await?,
+        CallbackCommand::EditPet(pet_id, field) => match parse_pet_field(&field) {
+            Some(field) => ask_for_pet_field(&bot, chat_id, pet_id, field, &connection).await?,
+            None => return Err(format!("Unknown pet field: {}", field).into()),
+        },
+        CallbackCommand::RemovePet(pet_id) => {
+            ask_to_remove_pet(&bot, chat_id, pet_id, &connection).await?
+        }
+        CallbackCommand::ConfirmRemovePet(pet_id) => {
+            handle_remove_pet(&bot, chat_id, pet_id, &connection, &tera).await?
+        }
+        CallbackCommand::EmergencyWizard(section) => match parse_section(section) {
+            Some(section) => {
+                ask_for_emergency_section(&bot, chat_id, section, true, &connection).await?
+            }
+            None => return Err(format!("Unknown emergency section: {}", section).into()),
+        },
+        CallbackCommand::EditEmergencySection(section) => match parse_section(section) {
+            Some(section) => {
+                ask_for_emergency_section(&bot, chat_id, section, false, &connection).await?
+            }
+            None => return Err(format!("Unknown emergency section: {}", section).into()),
+        },
+        CallbackCommand::AskForAttachment => ask_for_attachment(&bot, chat_id, &connection).await?,
+        CallbackCommand::RemoveAttachment(attachment_id) => {
+            handle_remove_attachment(&bot, chat_id, attachment_id, &connection, &keyring, &tera)
+                .await?
+        }
+        CallbackCommand::RegenerateInvite => {
+            handle_regenerate_invite(&bot, &me, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::RevokeInvite => {
+            handle_revoke_invite(&bot, &me, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::InviteQrCode => {
+            send_invite_qr_code(&bot, &me, chat_id, &connection, &tera).await?
+        }
+        CallbackCommand::ApproveContact(request_id) => {
+            handle_contact_request(&bot, chat_id, request_id, true, &connection).await?
+        }
+        CallbackCommand::RejectContact(request_id) => {
+            handle_contact_request(&bot, chat_id, request_id, false, &connection).await?
+        }
+        CallbackCommand::Contact(link_id) => {
+            show_emergency_contact(&bot, chat_id, link_id, &connection).await?
+        }
+        CallbackCommand::AskForExternalContact => {
+            ask_for_external_contact(&bot, chat_id, &connection, &notifiers).await?
+        }
+        CallbackCommand::SetContactTier(link_id, tier) => {
+            handle_set_contact_tier(&bot, chat_id, link_id, tier, &connection).await?
+        }
+        CallbackCommand::RemoveContact(link_id) => {
+            handle_remove_emergency_contact(&bot, &me, chat_id, link_id, &connection, &tera).await?
+        }
+        CallbackCommand::LeaveOwner(link_id) => {
+            ask_to_leave_owner(&bot, chat_id, link_id, &connection).await?
+        }
+        CallbackCommand::ConfirmLeaveOwner(link_id) => {
+            handle_leave_owner(&bot, chat_id, link_id, &connection, &tera).await?
         }
     };
 
@@ -124,46 +308,78 @@ async fn callback_handler(
     Ok(())
 }
 
+// dptree injects every dependency as an argument
+#[allow(clippy::too_many_arguments)]
 async fn message_handler(
     bot: Bot,
+    me: Me,
     message: Message,
     dialogue: BotDialogue,
     connection: DatabaseConnection,
+    keyring: Keyring,
+    notifiers: Notifiers,
     tera: Tera,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
     let text = message.text().unwrap_or_default();
-    let command = MessageCommand::parse(&text, "").ok();
+    let command = MessageCommand::parse(text, "").ok();
 
     // Match command first
     let next_state = if let Some(command) = command {
         match command {
-            MessageCommand::Start => show_start_info(&bot, &message, &connection, &tera).await?,
+            MessageCommand::Start(payload) => {
+                show_start_info(&bot, &message, &payload, &connection, &tera).await?
+            }
             MessageCommand::Menu | MessageCommand::OwnerMenu => {
-                show_owner_menu(&bot, message.chat.id, &connection, &tera).await?
+                show_owner_menu(&bot, &me, message.chat.id, &connection, &tera).await?
             }
             MessageCommand::ContactMenu => {
                 show_contact_menu(&bot, message.chat.id, &connection, &tera).await?
             }
-            MessageCommand::Enable => {
-                handle_enable_monitoring(&bot, message.chat.id, &connection).await?
-            }
-            MessageCommand::Disable => {
-                handle_disable_monitoring(&bot, message.chat.id, &connection).await?
+            MessageCommand::Language => {
+                show_language_menu(&bot, message.chat.id, &connection).await?
             }
         }
     // Match state second
     } else if let Some(state) = dialogue.get().await.ok().flatten() {
         match state {
-            BotDialogState::WaitingEmergencyText => {
-                set_emergency_info(&message, &connection).await?;
-                show_emergency_info(&bot, message.chat.id, &connection, &tera).await?
+            BotDialogState::WaitingEmergencySection { section, wizard } => {
+                handle_set_emergency_section(
+                    &bot,
+                    &message,
+                    section,
+                    wizard,
+                    &connection,
+                    &keyring,
+                    &tera,
+                )
+                .await?
             }
             BotDialogState::WaitingForInvite => {
-                accept_invite(&bot, &message, &connection).await?;
+                let invite_code = message.text().unwrap_or("");
+                accept_invite(&bot, message.chat.id, invite_code, &connection).await?;
                 show_contact_menu(&bot, message.chat.id, &connection, &tera).await?
             }
+            BotDialogState::WaitingForExternalContact => {
+                handle_add_external_contact(&bot, &me, &message, &connection, &notifiers, &tera)
+                    .await?
+            }
+            BotDialogState::WaitingForTimeZone => {
+                handle_set_time_zone(&bot, &message, &connection).await?;
+                show_monitoring_settings(&bot, message.chat.id, &connection, &tera).await?
+            }
+            BotDialogState::WaitingEmergencyAttachment => {
+                handle_add_attachment(&bot, &message, &connection, &keyring, &tera).await?
+            }
+            BotDialogState::WaitingForPetName => {
+                handle_add_pet(&bot, &message, &connection, &keyring, &tera).await?
+            }
+            BotDialogState::WaitingForPetField { pet_id, field } => {
+                handle_set_pet_field(&bot, &message, pet_id, field, &connection, &keyring, &tera)
+                    .await?
+            }
             BotDialogState::Idle => {
-                bot.send_message(message.chat.id, "Команда не найдена")
+                let language = get_language(&connection, message.chat.id).await;
+                bot.send_message(message.chat.id, Text::CommandNotFound.get(language))
                     .await?;
                 None
             }
@@ -194,7 +410,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     };
 
     let _guard = sentry::init((
-        config.sentry_url,
+        config.sentry_url.clone(),
         sentry::ClientOptions {
             release: sentry::release_name!(),
             ..Default::default()
@@ -204,32 +420,58 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let connection = connection::init().await?;
 
     let bot = Bot::from_env();
+    let notifiers = match Notifiers::new(bot.clone(), &config) {
+        Ok(notifiers) => notifiers,
+        Err(err) => panic!("Can't set up notifiers: {}", err),
+    };
+
+    // Shared by the scheduler and the dispatcher, the lock queries are short
+    let locks = scheduler::connect_locks(&config.db_url).await?;
+    if config.scheduler_enabled {
+        log::info!(
+            "Scheduling jobs every {} minutes...",
+            config.scheduler_interval_minutes
+        );
+        scheduler::spawn(
+            connection.clone(),
+            locks.clone(),
+            notifiers.clone(),
+            config.clone(),
+            tera.clone(),
+        );
+    }
+    scheduler::spawn_dispatcher(
+        connection.clone(),
+        locks.clone(),
+        bot.clone(),
+        notifiers.clone(),
+    );
 
     let handler = dptree::entry()
         .branch(
             Update::filter_message()
-                .enter_dialogue::<Message, InMemStorage<BotDialogState>, BotDialogState>()
+                .enter_dialogue::<Message, DatabaseStorage, BotDialogState>()
                 .inspect_async(update_profile_middleware)
                 .inspect_async(mark_alive_middleware)
                 .endpoint(message_handler),
         )
         .branch(
             Update::filter_callback_query()
-                .enter_dialogue::<CallbackQuery, InMemStorage<BotDialogState>, BotDialogState>()
+                .enter_dialogue::<CallbackQuery, DatabaseStorage, BotDialogState>()
                 .endpoint(callback_handler),
         );
 
-    log::info!("Started listening...");
-    Dispatcher::builder(bot, handler)
+    let dispatcher = Dispatcher::builder(bot.clone(), handler)
         .dependencies(dptree::deps![
-            InMemStorage::<BotDialogState>::new(),
+            DatabaseStorage::new(connection.clone()),
             connection,
+            config.keyring.clone(),
+            notifiers,
             tera
         ])
         .enable_ctrlc_handler()
-        .build()
-        .dispatch()
-        .await;
+        .build();
+    transport::dispatch(dispatcher, bot, config.transport).await;
 
     Ok(())
 }
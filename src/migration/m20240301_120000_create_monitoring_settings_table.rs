@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MonitoringSettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MonitoringSettings::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MonitoringSettings::ChatId)
+                            .unique_key()
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MonitoringSettings::CheckInIntervalHours)
+                            .integer()
+                            .not_null()
+                            .default(24),
+                    )
+                    .col(
+                        ColumnDef::new(MonitoringSettings::AlertGraceHours)
+                            .integer()
+                            .not_null()
+                            .default(24),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MonitoringSettings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MonitoringSettings {
+    Table,
+    Id,
+    ChatId,
+    CheckInIntervalHours,
+    AlertGraceHours,
+}
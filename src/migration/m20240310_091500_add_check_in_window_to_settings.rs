@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MonitoringSettings::Table)
+                    .add_column(ColumnDef::new(MonitoringSettings::CheckInWindowStart).integer())
+                    .add_column(ColumnDef::new(MonitoringSettings::CheckInWindowEnd).integer())
+                    .add_column(ColumnDef::new(MonitoringSettings::QuietHoursStart).integer())
+                    .add_column(ColumnDef::new(MonitoringSettings::QuietHoursEnd).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MonitoringSettings::Table)
+                    .drop_column(MonitoringSettings::CheckInWindowStart)
+                    .drop_column(MonitoringSettings::CheckInWindowEnd)
+                    .drop_column(MonitoringSettings::QuietHoursStart)
+                    .drop_column(MonitoringSettings::QuietHoursEnd)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MonitoringSettings {
+    Table,
+    CheckInWindowStart,
+    CheckInWindowEnd,
+    QuietHoursStart,
+    QuietHoursEnd,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PromptEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PromptEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PromptEvents::ChatId)
+                            .unique_key()
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PromptEvents::Timestamp)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PromptEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PromptEvents {
+    Table,
+    Id,
+    ChatId,
+    Timestamp,
+}
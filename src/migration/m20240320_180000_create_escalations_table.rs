@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Escalations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Escalations::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Escalations::ChatId)
+                            .unique_key()
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Escalations::Stage).integer().not_null())
+                    .col(
+                        ColumnDef::new(Escalations::UpdatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Escalations::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Escalations {
+    Table,
+    Id,
+    ChatId,
+    Stage,
+    UpdatedAt,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Incidents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Incidents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Incidents::ChatId).big_integer().not_null())
+                    .col(ColumnDef::new(Incidents::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(Incidents::ResolvedAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Incidents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Incidents {
+    Table,
+    Id,
+    ChatId,
+    CreatedAt,
+    ResolvedAt,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Incidents::Table)
+                    .add_column(ColumnDef::new(Incidents::AcknowledgedBy).big_integer())
+                    .add_column(ColumnDef::new(Incidents::AcknowledgedAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Incidents::Table)
+                    .drop_column(Incidents::AcknowledgedBy)
+                    .drop_column(Incidents::AcknowledgedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Incidents {
+    Table,
+    AcknowledgedBy,
+    AcknowledgedAt,
+}
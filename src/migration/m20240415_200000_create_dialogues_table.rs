@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Dialogues::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Dialogues::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Dialogues::ChatId)
+                            .unique_key()
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Dialogues::State).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Dialogues::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Dialogues {
+    Table,
+    Id,
+    ChatId,
+    State,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Pets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Pets::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Pets::ChatId).big_integer().not_null())
+                    .col(ColumnDef::new(Pets::Name).string().not_null())
+                    .col(ColumnDef::new(Pets::Species).string())
+                    .col(ColumnDef::new(Pets::PhotoFileId).string())
+                    .col(ColumnDef::new(Pets::MedicalNotes).text())
+                    .col(ColumnDef::new(Pets::Diet).text())
+                    .col(ColumnDef::new(Pets::VetContact).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Pets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Pets {
+    Table,
+    Id,
+    ChatId,
+    Name,
+    Species,
+    PhotoFileId,
+    MedicalNotes,
+    Diet,
+    VetContact,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing free-text info becomes the "special instructions" section
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .add_column(
+                        ColumnDef::new(EmergencyInfo::Section)
+                            .integer()
+                            .not_null()
+                            .default(5),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE emergency_info DROP CONSTRAINT IF EXISTS emergency_info_chat_id_key",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("emergency_info_chat_id_section_key")
+                    .table(EmergencyInfo::Table)
+                    .col(EmergencyInfo::ChatId)
+                    .col(EmergencyInfo::Section)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("emergency_info_chat_id_section_key")
+                    .table(EmergencyInfo::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only one section per owner fits the old layout
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM emergency_info WHERE id NOT IN \
+                 (SELECT MIN(id) FROM emergency_info GROUP BY chat_id)",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .drop_column(EmergencyInfo::Section)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE emergency_info \
+                 ADD CONSTRAINT emergency_info_chat_id_key UNIQUE (chat_id)",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmergencyInfo {
+    Table,
+    ChatId,
+    Section,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmergencyAttachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmergencyAttachments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EmergencyAttachments::ChatId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmergencyAttachments::Kind)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(EmergencyAttachments::FileId).string())
+                    .col(ColumnDef::new(EmergencyAttachments::Caption).text())
+                    .col(ColumnDef::new(EmergencyAttachments::Latitude).double())
+                    .col(ColumnDef::new(EmergencyAttachments::Longitude).double())
+                    .col(
+                        ColumnDef::new(EmergencyAttachments::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EmergencyAttachments::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmergencyAttachments {
+    Table,
+    Id,
+    ChatId,
+    Kind,
+    FileId,
+    Caption,
+    Latitude,
+    Longitude,
+    CreatedAt,
+}
@@ -0,0 +1,135 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use crate::config::read_keyring;
+use crate::crypto::Sealed;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .add_column(ColumnDef::new(EmergencyInfo::Ciphertext).binary())
+                    .add_column(ColumnDef::new(EmergencyInfo::Nonce).binary())
+                    .add_column(ColumnDef::new(EmergencyInfo::DataKey).binary())
+                    .add_column(ColumnDef::new(EmergencyInfo::KeyId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Encrypt the existing plain text rows one by one
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, text FROM emergency_info",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let text: String = row.try_get("", "text")?;
+            let sealed = keyring
+                .seal(&text)
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE emergency_info \
+                     SET ciphertext = $1, nonce = $2, data_key = $3, key_id = $4 WHERE id = $5",
+                    [
+                        sealed.ciphertext.into(),
+                        sealed.nonce.into(),
+                        sealed.data_key.into(),
+                        sealed.key_id.into(),
+                        id.into(),
+                    ],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .modify_column(ColumnDef::new(EmergencyInfo::Ciphertext).not_null())
+                    .modify_column(ColumnDef::new(EmergencyInfo::Nonce).not_null())
+                    .modify_column(ColumnDef::new(EmergencyInfo::DataKey).not_null())
+                    .modify_column(ColumnDef::new(EmergencyInfo::KeyId).not_null())
+                    .drop_column(EmergencyInfo::Text)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .add_column(ColumnDef::new(EmergencyInfo::Text).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, ciphertext, nonce, data_key, key_id FROM emergency_info",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let sealed = Sealed {
+                ciphertext: row.try_get("", "ciphertext")?,
+                nonce: row.try_get("", "nonce")?,
+                data_key: row.try_get("", "data_key")?,
+                key_id: row.try_get("", "key_id")?,
+            };
+            let text = keyring
+                .open(&sealed)
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE emergency_info SET text = $1 WHERE id = $2",
+                    [text.into(), id.into()],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyInfo::Table)
+                    .modify_column(ColumnDef::new(EmergencyInfo::Text).not_null())
+                    .drop_column(EmergencyInfo::Ciphertext)
+                    .drop_column(EmergencyInfo::Nonce)
+                    .drop_column(EmergencyInfo::DataKey)
+                    .drop_column(EmergencyInfo::KeyId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmergencyInfo {
+    Table,
+    Text,
+    Ciphertext,
+    Nonce,
+    DataKey,
+    KeyId,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Codes handed out before this stay valid for one more week
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invites::Table)
+                    .add_column(
+                        ColumnDef::new(Invites::ExpiresAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::cust("(NOW() AT TIME ZONE 'UTC') + INTERVAL '7 days'")),
+                    )
+                    .add_column(
+                        ColumnDef::new(Invites::MaxUses)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .add_column(
+                        ColumnDef::new(Invites::Uses)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Invites::Revoked)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invites::Table)
+                    .drop_column(Invites::ExpiresAt)
+                    .drop_column(Invites::MaxUses)
+                    .drop_column(Invites::Uses)
+                    .drop_column(Invites::Revoked)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Invites {
+    Table,
+    ExpiresAt,
+    MaxUses,
+    Uses,
+    Revoked,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ContactRequests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ContactRequests::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::PrimaryOwnerChatId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::SecondaryOwnerChatId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ContactRequests::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ContactRequests {
+    Table,
+    Id,
+    PrimaryOwnerChatId,
+    SecondaryOwnerChatId,
+    CreatedAt,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing links get the migration time, the real date is unknown
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .add_column(
+                        ColumnDef::new(SecondaryOwners::CreatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Expr::cust("(NOW() AT TIME ZONE 'UTC')")),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .drop_column(SecondaryOwners::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SecondaryOwners {
+    Table,
+    CreatedAt,
+}
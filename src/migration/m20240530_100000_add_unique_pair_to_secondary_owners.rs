@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep the oldest of the duplicated links and drop owners backing up themselves
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM secondary_owners WHERE id NOT IN \
+                 (SELECT MIN(id) FROM secondary_owners \
+                 GROUP BY primary_owner_chat_id, secondary_owner_chat_id)",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM secondary_owners \
+                 WHERE primary_owner_chat_id = secondary_owner_chat_id",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("secondary_owners_pair_key")
+                    .table(SecondaryOwners::Table)
+                    .col(SecondaryOwners::PrimaryOwnerChatId)
+                    .col(SecondaryOwners::SecondaryOwnerChatId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE secondary_owners ADD CONSTRAINT secondary_owners_not_self_check \
+                 CHECK (primary_owner_chat_id <> secondary_owner_chat_id)",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE secondary_owners DROP CONSTRAINT secondary_owners_not_self_check",
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("secondary_owners_pair_key")
+                    .table(SecondaryOwners::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SecondaryOwners {
+    Table,
+    PrimaryOwnerChatId,
+    SecondaryOwnerChatId,
+}
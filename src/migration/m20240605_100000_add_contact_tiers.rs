@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Everyone starts in the first tier, as they were all notified at once before
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .add_column(
+                        ColumnDef::new(SecondaryOwners::Tier)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Incidents::Table)
+                    .add_column(ColumnDef::new(Incidents::NotifiedTier).integer())
+                    .add_column(ColumnDef::new(Incidents::NotifiedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing incidents already reached every contact
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE incidents SET notified_tier = 3, notified_at = created_at")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Incidents::Table)
+                    .drop_column(Incidents::NotifiedTier)
+                    .drop_column(Incidents::NotifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .drop_column(SecondaryOwners::Tier)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SecondaryOwners {
+    Table,
+    Tier,
+}
+
+#[derive(DeriveIden)]
+enum Incidents {
+    Table,
+    NotifiedTier,
+    NotifiedAt,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Contacts without Telegram are reached by email or SMS, so they have no chat
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .modify_column(
+                        ColumnDef::new(SecondaryOwners::SecondaryOwnerChatId)
+                            .big_integer()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(SecondaryOwners::Channel)
+                            .string()
+                            .not_null()
+                            .default("telegram"),
+                    )
+                    .add_column(ColumnDef::new(SecondaryOwners::Address).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("secondary_owners_address_key")
+                    .table(SecondaryOwners::Table)
+                    .col(SecondaryOwners::PrimaryOwnerChatId)
+                    .col(SecondaryOwners::Address)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE secondary_owners ADD CONSTRAINT secondary_owners_recipient_check \
+                 CHECK ((channel = 'telegram' AND secondary_owner_chat_id IS NOT NULL \
+                 AND address IS NULL) OR (channel <> 'telegram' \
+                 AND secondary_owner_chat_id IS NULL AND address IS NOT NULL))",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM secondary_owners WHERE channel <> 'telegram'")
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE secondary_owners DROP CONSTRAINT secondary_owners_recipient_check",
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("secondary_owners_address_key")
+                    .table(SecondaryOwners::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SecondaryOwners::Table)
+                    .modify_column(
+                        ColumnDef::new(SecondaryOwners::SecondaryOwnerChatId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .drop_column(SecondaryOwners::Channel)
+                    .drop_column(SecondaryOwners::Address)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SecondaryOwners {
+    Table,
+    PrimaryOwnerChatId,
+    SecondaryOwnerChatId,
+    Channel,
+    Address,
+}
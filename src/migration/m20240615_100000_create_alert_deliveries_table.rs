@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlertDeliveries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AlertDeliveries::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AlertDeliveries::IncidentId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AlertDeliveries::LinkId).integer().not_null())
+                    .col(
+                        ColumnDef::new(AlertDeliveries::Status)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(AlertDeliveries::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(AlertDeliveries::LastError).text())
+                    .col(
+                        ColumnDef::new(AlertDeliveries::NextAttemptAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AlertDeliveries::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AlertDeliveries::SentAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        // A contact gets each incident's alert once
+        manager
+            .create_index(
+                Index::create()
+                    .name("alert_deliveries_incident_link_key")
+                    .table(AlertDeliveries::Table)
+                    .col(AlertDeliveries::IncidentId)
+                    .col(AlertDeliveries::LinkId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AlertDeliveries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AlertDeliveries {
+    Table,
+    Id,
+    IncidentId,
+    LinkId,
+    Status,
+    Attempts,
+    LastError,
+    NextAttemptAt,
+    CreatedAt,
+    SentAt,
+}
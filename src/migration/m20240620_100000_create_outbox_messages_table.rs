@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OutboxMessages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OutboxMessages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OutboxMessages::ChatId).big_integer())
+                    .col(ColumnDef::new(OutboxMessages::LinkId).integer())
+                    .col(
+                        ColumnDef::new(OutboxMessages::Payload)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OutboxMessages::Status)
+                            .string()
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(OutboxMessages::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(OutboxMessages::LastError).text())
+                    .col(
+                        ColumnDef::new(OutboxMessages::NextAttemptAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OutboxMessages::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OutboxMessages::SentAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The dispatcher only ever looks at pending messages
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX outbox_messages_pending_idx ON outbox_messages (next_attempt_at) \
+                 WHERE status = 'pending'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OutboxMessages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OutboxMessages {
+    Table,
+    Id,
+    ChatId,
+    LinkId,
+    Payload,
+    Status,
+    Attempts,
+    LastError,
+    NextAttemptAt,
+    CreatedAt,
+    SentAt,
+}
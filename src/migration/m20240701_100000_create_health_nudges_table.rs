@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(HealthNudges::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HealthNudges::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(HealthNudges::ChatId)
+                            .unique_key()
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(HealthNudges::Timestamp)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HealthNudges::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum HealthNudges {
+    Table,
+    Id,
+    ChatId,
+    Timestamp,
+}
@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use crate::config::read_keyring;
+use crate::crypto::Sealed;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        // All null when the attachment has no caption
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyAttachments::Table)
+                    .add_column(ColumnDef::new(EmergencyAttachments::CaptionCiphertext).binary())
+                    .add_column(ColumnDef::new(EmergencyAttachments::CaptionNonce).binary())
+                    .add_column(ColumnDef::new(EmergencyAttachments::CaptionDataKey).binary())
+                    .add_column(ColumnDef::new(EmergencyAttachments::CaptionKeyId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, caption FROM emergency_attachments WHERE caption IS NOT NULL",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let caption: String = row.try_get("", "caption")?;
+            let sealed = keyring
+                .seal(&caption)
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE emergency_attachments \
+                     SET caption_ciphertext = $1, caption_nonce = $2, caption_data_key = $3, \
+                     caption_key_id = $4 WHERE id = $5",
+                    [
+                        sealed.ciphertext.into(),
+                        sealed.nonce.into(),
+                        sealed.data_key.into(),
+                        sealed.key_id.into(),
+                        id.into(),
+                    ],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyAttachments::Table)
+                    .drop_column(EmergencyAttachments::Caption)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyAttachments::Table)
+                    .add_column(ColumnDef::new(EmergencyAttachments::Caption).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, caption_ciphertext, caption_nonce, caption_data_key, caption_key_id \
+                 FROM emergency_attachments WHERE caption_ciphertext IS NOT NULL",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let sealed = Sealed {
+                ciphertext: row.try_get("", "caption_ciphertext")?,
+                nonce: row.try_get("", "caption_nonce")?,
+                data_key: row.try_get("", "caption_data_key")?,
+                key_id: row.try_get("", "caption_key_id")?,
+            };
+            let caption = keyring
+                .open(&sealed)
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE emergency_attachments SET caption = $1 WHERE id = $2",
+                    [caption.into(), id.into()],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(EmergencyAttachments::Table)
+                    .drop_column(EmergencyAttachments::CaptionCiphertext)
+                    .drop_column(EmergencyAttachments::CaptionNonce)
+                    .drop_column(EmergencyAttachments::CaptionDataKey)
+                    .drop_column(EmergencyAttachments::CaptionKeyId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EmergencyAttachments {
+    Table,
+    Caption,
+    CaptionCiphertext,
+    CaptionNonce,
+    CaptionDataKey,
+    CaptionKeyId,
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The per-section dialogue states were merged into `WaitingEmergencySection`.
+// Unit states are stored as JSON strings, `->` on them is just NULL.
+const SECTION_STATES: [(&str, &str); 6] = [
+    ("WaitingEmergencyHomeAccess", "HomeAccess"),
+    ("WaitingEmergencyDocuments", "Documents"),
+    ("WaitingEmergencyHealth", "Health"),
+    ("WaitingEmergencyDiet", "Diet"),
+    ("WaitingEmergencyInstructions", "Instructions"),
+    ("WaitingEmergencyVet", "Vet"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (state, section) in SECTION_STATES {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    "UPDATE dialogues SET state = jsonb_build_object(\
+                     'WaitingEmergencySection', jsonb_build_object(\
+                     'section', '{section}', 'wizard', state::jsonb -> '{state}' -> 'wizard'))::text \
+                     WHERE state::jsonb -> '{state}' IS NOT NULL",
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (state, section) in SECTION_STATES {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    "UPDATE dialogues SET state = jsonb_build_object(\
+                     '{state}', jsonb_build_object(\
+                     'wizard', state::jsonb -> 'WaitingEmergencySection' -> 'wizard'))::text \
+                     WHERE state::jsonb -> 'WaitingEmergencySection' ->> 'section' = '{section}'",
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+use serde_json::json;
+
+use crate::config::read_keyring;
+use crate::crypto::Sealed;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        // Medical notes, diet and vet contact are sealed together as JSON,
+        // all null when the pet has none of them
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Pets::Table)
+                    .add_column(ColumnDef::new(Pets::CareCiphertext).binary())
+                    .add_column(ColumnDef::new(Pets::CareNonce).binary())
+                    .add_column(ColumnDef::new(Pets::CareDataKey).binary())
+                    .add_column(ColumnDef::new(Pets::CareKeyId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, medical_notes, diet, vet_contact FROM pets \
+                 WHERE medical_notes IS NOT NULL OR diet IS NOT NULL OR vet_contact IS NOT NULL",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let care = json!({
+                "medical_notes": row.try_get::<Option<String>>("", "medical_notes")?,
+                "diet": row.try_get::<Option<String>>("", "diet")?,
+                "vet_contact": row.try_get::<Option<String>>("", "vet_contact")?,
+            });
+            let sealed = keyring
+                .seal(&care.to_string())
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE pets \
+                     SET care_ciphertext = $1, care_nonce = $2, care_data_key = $3, \
+                     care_key_id = $4 WHERE id = $5",
+                    [
+                        sealed.ciphertext.into(),
+                        sealed.nonce.into(),
+                        sealed.data_key.into(),
+                        sealed.key_id.into(),
+                        id.into(),
+                    ],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Pets::Table)
+                    .drop_column(Pets::MedicalNotes)
+                    .drop_column(Pets::Diet)
+                    .drop_column(Pets::VetContact)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let keyring = read_keyring().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Pets::Table)
+                    .add_column(ColumnDef::new(Pets::MedicalNotes).text())
+                    .add_column(ColumnDef::new(Pets::Diet).text())
+                    .add_column(ColumnDef::new(Pets::VetContact).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT id, care_ciphertext, care_nonce, care_data_key, care_key_id \
+                 FROM pets WHERE care_ciphertext IS NOT NULL",
+            ))
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let sealed = Sealed {
+                ciphertext: row.try_get("", "care_ciphertext")?,
+                nonce: row.try_get("", "care_nonce")?,
+                data_key: row.try_get("", "care_data_key")?,
+                key_id: row.try_get("", "care_key_id")?,
+            };
+            let care = keyring
+                .open(&sealed)
+                .map_err(|err| DbErr::Custom(err.to_string()))?;
+            let care: serde_json::Value =
+                serde_json::from_str(&care).map_err(|err| DbErr::Custom(err.to_string()))?;
+            let get_field = |name: &str| care[name].as_str().map(|value| value.to_string());
+            connection
+                .execute(Statement::from_sql_and_values(
+                    backend,
+                    "UPDATE pets SET medical_notes = $1, diet = $2, vet_contact = $3 WHERE id = $4",
+                    [
+                        get_field("medical_notes").into(),
+                        get_field("diet").into(),
+                        get_field("vet_contact").into(),
+                        id.into(),
+                    ],
+                ))
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Pets::Table)
+                    .drop_column(Pets::CareCiphertext)
+                    .drop_column(Pets::CareNonce)
+                    .drop_column(Pets::CareDataKey)
+                    .drop_column(Pets::CareKeyId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Pets {
+    Table,
+    MedicalNotes,
+    Diet,
+    VetContact,
+    CareCiphertext,
+    CareNonce,
+    CareDataKey,
+    CareKeyId,
+}
@@ -7,6 +7,34 @@ mod m20240114_210132_create_invites_table;
 mod m20240114_210350_create_secondary_owners_table;
 mod m20240115_192831_create_profiles_table;
 mod m20240222_210939_enable_monitoring;
+mod m20240301_120000_create_monitoring_settings_table;
+mod m20240310_090000_add_time_zone_to_profiles;
+mod m20240310_091500_add_check_in_window_to_settings;
+mod m20240310_093000_create_prompt_events_table;
+mod m20240320_180000_create_escalations_table;
+mod m20240402_110000_create_incidents_table;
+mod m20240405_143000_add_acknowledgement_to_incidents;
+mod m20240415_200000_create_dialogues_table;
+mod m20240420_100000_add_language_to_profiles;
+mod m20240425_120000_create_pets_table;
+mod m20240501_090000_add_section_to_emergency_info;
+mod m20240505_100000_create_emergency_attachments_table;
+mod m20240510_090000_encrypt_emergency_info;
+mod m20240515_100000_add_limits_to_invites;
+mod m20240520_100000_create_contact_requests_table;
+mod m20240525_100000_add_created_at_to_secondary_owners;
+mod m20240530_100000_add_unique_pair_to_secondary_owners;
+mod m20240605_100000_add_contact_tiers;
+mod m20240610_100000_add_channels_to_secondary_owners;
+mod m20240615_100000_create_alert_deliveries_table;
+mod m20240620_100000_create_outbox_messages_table;
+mod m20240625_100000_add_unreachable_to_profiles;
+mod m20240701_100000_create_health_nudges_table;
+mod m20240705_100000_encrypt_attachment_captions;
+mod m20240710_100000_add_invite_to_contact_requests;
+mod m20240715_100000_merge_emergency_dialogue_states;
+mod m20240720_100000_add_check_in_prompt_to_outbox_messages;
+mod m20240725_100000_encrypt_pet_care_notes;
 
 pub struct Migrator;
 
@@ -21,6 +49,34 @@ impl MigratorTrait for Migrator {
             Box::new(m20240114_210350_create_secondary_owners_table::Migration),
             Box::new(m20240115_192831_create_profiles_table::Migration),
             Box::new(m20240222_210939_enable_monitoring::Migration),
+            Box::new(m20240301_120000_create_monitoring_settings_table::Migration),
+            Box::new(m20240310_090000_add_time_zone_to_profiles::Migration),
+            Box::new(m20240310_091500_add_check_in_window_to_settings::Migration),
+            Box::new(m20240310_093000_create_prompt_events_table::Migration),
+            Box::new(m20240320_180000_create_escalations_table::Migration),
+            Box::new(m20240402_110000_create_incidents_table::Migration),
+            Box::new(m20240405_143000_add_acknowledgement_to_incidents::Migration),
+            Box::new(m20240415_200000_create_dialogues_table::Migration),
+            Box::new(m20240420_100000_add_language_to_profiles::Migration),
+            Box::new(m20240425_120000_create_pets_table::Migration),
+            Box::new(m20240501_090000_add_section_to_emergency_info::Migration),
+            Box::new(m20240505_100000_create_emergency_attachments_table::Migration),
+            Box::new(m20240510_090000_encrypt_emergency_info::Migration),
+            Box::new(m20240515_100000_add_limits_to_invites::Migration),
+            Box::new(m20240520_100000_create_contact_requests_table::Migration),
+            Box::new(m20240525_100000_add_created_at_to_secondary_owners::Migration),
+            Box::new(m20240530_100000_add_unique_pair_to_secondary_owners::Migration),
+            Box::new(m20240605_100000_add_contact_tiers::Migration),
+            Box::new(m20240610_100000_add_channels_to_secondary_owners::Migration),
+            Box::new(m20240615_100000_create_alert_deliveries_table::Migration),
+            Box::new(m20240620_100000_create_outbox_messages_table::Migration),
+            Box::new(m20240625_100000_add_unreachable_to_profiles::Migration),
+            Box::new(m20240701_100000_create_health_nudges_table::Migration),
+            Box::new(m20240705_100000_encrypt_attachment_captions::Migration),
+            Box::new(m20240710_100000_add_invite_to_contact_requests::Migration),
+            Box::new(m20240715_100000_merge_emergency_dialogue_states::Migration),
+            Box::new(m20240720_100000_add_check_in_prompt_to_outbox_messages::Migration),
+            Box::new(m20240725_100000_encrypt_pet_care_notes::Migration),
         ]
     }
 }
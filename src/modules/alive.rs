@@ -1,13 +1,36 @@
 use std::error::Error;
 
 use chrono::prelude::*;
-use sea_orm::{prelude::*, sea_query::OnConflict, ActiveValue};
-use teloxide::{prelude::*, types::MessageId};
+use sea_orm::{prelude::*, sea_query::OnConflict, ActiveValue, TransactionTrait};
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId},
+};
+use tera::{Context, Tera};
 
-use crate::{entity::alive_events, types::BotDialogState};
+use crate::{
+    entity::{alive_events, escalations::EscalationStage, profiles::Language, prompt_events},
+    escalations::utils::reset_stage,
+    i18n::{render, Text},
+    notifiers::Notification,
+    outbox::utils::{cancel_check_in_prompts, enqueue_delete_message, enqueue_notice},
+    profiles::utils::{get_contact_language, get_display_name, select_emergency_contacts},
+    types::BotDialogState,
+};
 
-pub async fn mark_alive(
-    connection: &DatabaseConnection,
+use super::incidents::resolve_incident_and_notify;
+
+pub fn get_alive_keyboard(language: Language) -> InlineKeyboardMarkup {
+    let keyboard = vec![vec![InlineKeyboardButton::callback(
+        Text::AliveButton.get(language),
+        "/mark_alive",
+    )]];
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub async fn mark_alive<C: ConnectionTrait>(
+    connection: &C,
     chat_id: ChatId,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     alive_events::Entity::insert(alive_events::ActiveModel {
@@ -26,13 +49,70 @@ pub async fn mark_alive(
     Ok(())
 }
 
+// Called by the outbox dispatcher once a check-in prompt is delivered
+pub async fn mark_prompted<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    prompt_events::Entity::insert(prompt_events::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        timestamp: ActiveValue::Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(prompt_events::Column::ChatId)
+            .update_column(prompt_events::Column::Timestamp)
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+// Marks the owner alive and stops the escalation ladder, contacts who were
+// already notified get told that the owner is back. Meant to run in a
+// transaction, so the notices are only queued if the check-in sticks.
+pub async fn check_in<C: ConnectionTrait>(
+    chat_id: ChatId,
+    connection: &C,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    mark_alive(connection, chat_id).await?;
+    cancel_check_in_prompts(connection, chat_id).await?;
+
+    let stage = reset_stage(connection, chat_id).await?;
+    if stage == EscalationStage::HeadsUp {
+        if let Some(recipient) = select_emergency_contacts(chat_id).one(connection).await? {
+            let language = get_contact_language(connection, &recipient).await;
+            let username = get_display_name(connection, chat_id, language).await;
+            let mut context = Context::new();
+            context.insert("username", &username);
+            let message = render(tera, language, "check_in_contact.html", &context).unwrap();
+
+            log::info!("Notifying {:?}", recipient);
+            let notification = Notification {
+                subject: Text::NotificationSubject.format(language, &[("username", &username)]),
+                html: message,
+                ..Default::default()
+            };
+            enqueue_notice(connection, &recipient, &notification).await?;
+        }
+    }
+
+    resolve_incident_and_notify(chat_id, connection, tera).await
+}
+
 pub async fn mark_alive_callback(
-    bot: &Bot,
     chat_id: ChatId,
     message_id: MessageId,
     connection: &DatabaseConnection,
+    tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
-    mark_alive(connection, chat_id).await?;
-    bot.delete_message(chat_id, message_id).await?;
+    // The prompt goes away only once the check-in is saved
+    let transaction = connection.begin().await?;
+    check_in(chat_id, &transaction, tera).await?;
+    enqueue_delete_message(&transaction, chat_id, message_id).await?;
+    transaction.commit().await?;
     Ok(None)
 }
@@ -7,23 +7,68 @@ use teloxide::{
 use tera::{Context, Tera};
 
 use crate::{
-    entity::{profiles, secondary_owners},
+    entity::{
+        incidents,
+        profiles::{self, Language},
+        secondary_owners,
+    },
+    i18n::{render, Text},
+    incidents::utils::select_open_incidents_for_contact,
+    profiles::utils::{get_display_name, get_language, select_backed_up_owners},
     types::BotDialogState,
 };
 
-fn format_owners(owners: Vec<profiles::Model>) -> String {
+fn format_owners(owners: Vec<profiles::Model>, language: Language) -> String {
     let owners = owners
         .iter()
         .map(|profile| format!("@{}", profile.username.clone()))
         .collect::<Vec<_>>();
 
     if owners.is_empty() {
-        "Нет контактов".to_string()
+        Text::NoContacts.get(language).to_string()
     } else {
         owners.join("\n")
     }
 }
 
+async fn format_incidents(
+    connection: &DatabaseConnection,
+    incidents: &[incidents::Model],
+    language: Language,
+) -> Option<String> {
+    let mut lines = vec![];
+    for incident in incidents {
+        let username = get_display_name(connection, ChatId(incident.chat_id), language).await;
+        let status = match incident.acknowledged_by {
+            Some(chat_id) => Text::IncidentAcknowledgedBy.format(
+                language,
+                &[(
+                    "contact",
+                    &get_display_name(connection, ChatId(chat_id), language).await,
+                )],
+            ),
+            None => Text::IncidentNotAcknowledged.get(language).to_string(),
+        };
+        lines.push(Text::IncidentLine.format(
+            language,
+            &[
+                ("username", &username),
+                (
+                    "since",
+                    &incident.created_at.format("%d.%m %H:%M UTC").to_string(),
+                ),
+                ("status", &status),
+            ],
+        ));
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
 async fn get_primary_owners(
     connection: &DatabaseConnection,
     chat_id: ChatId,
@@ -42,16 +87,52 @@ async fn get_primary_owners(
         .unwrap_or(vec![])
 }
 
-pub fn get_secondary_menu_keyboard() -> InlineKeyboardMarkup {
+pub async fn get_secondary_menu_keyboard(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    incidents: &[incidents::Model],
+    language: Language,
+) -> InlineKeyboardMarkup {
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
+    for incident in incidents.iter().filter(|x| x.acknowledged_by.is_none()) {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            Text::AcknowledgeForButton.format(
+                language,
+                &[(
+                    "username",
+                    &get_display_name(connection, ChatId(incident.chat_id), language).await,
+                )],
+            ),
+            format!("/acknowledge_incident {}", incident.id),
+        )]);
+    }
+
+    let links = select_backed_up_owners(chat_id)
+        .all(connection)
+        .await
+        .unwrap_or(vec![]);
+    for link in links {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            Text::LeaveOwnerButton.format(
+                language,
+                &[(
+                    "username",
+                    &get_display_name(connection, ChatId(link.primary_owner_chat_id), language)
+                        .await,
+                )],
+            ),
+            format!("/leave_owner {}", link.id),
+        )]);
+    }
+
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "👈 Меню владельца питомца",
+        Text::OwnerMenuButton.get(language),
         "/owner_menu",
     )]);
 
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "🤝 Принять приглашение",
+        Text::AcceptInviteButton.get(language),
         "/ask_for_invite",
     )]);
 
@@ -64,13 +145,23 @@ pub async fn show_contact_menu(
     connection: &DatabaseConnection,
     tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
     let primary_owners = get_primary_owners(connection, chat_id).await;
-    let primary_owners = format_owners(primary_owners);
+    let primary_owners = format_owners(primary_owners, language);
+
+    let incidents = select_open_incidents_for_contact(chat_id)
+        .all(connection)
+        .await
+        .unwrap_or(vec![]);
 
-    let keyboard = get_secondary_menu_keyboard();
+    let keyboard = get_secondary_menu_keyboard(connection, chat_id, &incidents, language).await;
     let mut context = Context::new();
     context.insert("primary_owners", &primary_owners);
-    let answer = tera.render("contact_menu.html", &context).unwrap();
+    context.insert(
+        "incidents",
+        &format_incidents(connection, &incidents, language).await,
+    );
+    let answer = render(tera, language, "contact_menu.html", &context).unwrap();
     bot.send_message(chat_id, answer)
         .parse_mode(ParseMode::Html)
         .reply_markup(keyboard)
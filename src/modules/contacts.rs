@@ -0,0 +1,360 @@
+use sea_orm::{prelude::*, Iterable, TransactionTrait};
+use std::error::Error;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, Me},
+};
+use tera::Tera;
+
+use crate::{
+    entity::{
+        profiles::Language,
+        secondary_owners::{self, ContactChannel, ContactTier},
+    },
+    i18n::Text,
+    notifiers::{Notifiers, NotifyError},
+    outbox::utils::{enqueue_message, fail_pending_messages},
+    profiles::utils::{
+        add_external_contact, get_contact_name, get_display_name, get_language, mark_unreachable,
+        parse_contact_address, remove_emergency_contact, select_backed_up_owners,
+        select_emergency_contacts, set_contact_tier,
+    },
+    types::BotDialogState,
+};
+
+use super::{contact_menu::show_contact_menu, owner_menu::show_owner_menu};
+
+pub fn get_tier_label(tier: ContactTier) -> Text {
+    match tier {
+        ContactTier::Primary => Text::TierPrimary,
+        ContactTier::Secondary => Text::TierSecondary,
+        ContactTier::Tertiary => Text::TierTertiary,
+    }
+}
+
+fn get_contact_keyboard(
+    link: &secondary_owners::Model,
+    language: Language,
+) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(
+        ContactTier::iter()
+            .map(|tier| {
+                let label = get_tier_label(tier).get(language);
+                InlineKeyboardButton::callback(
+                    if tier == link.tier {
+                        format!("✅ {}", label)
+                    } else {
+                        label.to_string()
+                    },
+                    format!("/set_contact_tier {} {}", link.id, tier.to_value()),
+                )
+            })
+            .collect(),
+    );
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::RemoveContactButton.get(language),
+        format!("/remove_contact {}", link.id),
+    )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::OwnerMenuButton.get(language),
+        "/owner_menu",
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+fn get_leave_owner_keyboard(link_id: i32, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![
+        InlineKeyboardButton::callback(
+            Text::ConfirmLeaveOwnerButton.get(language),
+            format!("/confirm_leave_owner {}", link_id),
+        ),
+        InlineKeyboardButton::callback(Text::CancelButton.get(language), "/contact_menu"),
+    ]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+// Tells both sides that the link is gone, each in their own language. Email and
+// SMS contacts were added by the owner alone, so they aren't bothered.
+async fn get_unlinked_notices(
+    connection: &DatabaseConnection,
+    link: &secondary_owners::Model,
+) -> Vec<(ChatId, String)> {
+    let owner_chat_id = ChatId(link.primary_owner_chat_id);
+
+    let language = get_language(connection, owner_chat_id).await;
+    let contact = get_contact_name(connection, link, language).await;
+    let mut notices = vec![(
+        owner_chat_id,
+        Text::ContactRemoved.format(language, &[("contact", &contact)]),
+    )];
+
+    if let Some(contact_chat_id) = link.secondary_owner_chat_id.map(ChatId) {
+        let language = get_language(connection, contact_chat_id).await;
+        let username = get_display_name(connection, owner_chat_id, language).await;
+        notices.push((
+            contact_chat_id,
+            Text::OwnerRemoved.format(language, &[("username", &username)]),
+        ));
+    }
+    notices
+}
+
+// Returns `false` if the link is already gone. The other side's notice is
+// queued with the removal, whoever removed it gets theirs right away.
+async fn unlink(
+    bot: &Bot,
+    chat_id: ChatId,
+    link: &secondary_owners::Model,
+    connection: &DatabaseConnection,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let notices = get_unlinked_notices(connection, link).await;
+
+    let transaction = connection.begin().await?;
+    if !remove_emergency_contact(&transaction, link).await? {
+        return Ok(false);
+    }
+    let mut answer = None;
+    for (recipient, message) in notices {
+        if recipient == chat_id {
+            answer = Some(message);
+        } else {
+            enqueue_message(&transaction, recipient, message, None, None).await?;
+        }
+    }
+    transaction.commit().await?;
+
+    if let Some(answer) = answer {
+        bot.send_message(chat_id, answer).await?;
+    }
+    Ok(true)
+}
+
+// Called when Telegram says a chat is blocked or gone. The owners it backs up
+// are warned once, their alerts won't reach it until the user writes again.
+pub async fn handle_unreachable_chat(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    error: &NotifyError,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let transaction = connection.begin().await?;
+    if !mark_unreachable(&transaction, chat_id).await? {
+        return Ok(());
+    }
+    log::warn!("{:?} is unreachable: {}", chat_id, error);
+    fail_pending_messages(&transaction, chat_id, error).await?;
+
+    let links = select_backed_up_owners(chat_id).all(connection).await?;
+    for link in links {
+        let owner_chat_id = ChatId(link.primary_owner_chat_id);
+        let language = get_language(connection, owner_chat_id).await;
+        let contact = get_contact_name(connection, &link, language).await;
+        let message = Text::ContactUnreachable.format(language, &[("contact", &contact)]);
+        enqueue_message(&transaction, owner_chat_id, message, None, None).await?;
+    }
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+pub async fn show_emergency_contact(
+    bot: &Bot,
+    chat_id: ChatId,
+    link_id: i32,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let language = get_language(connection, chat_id).await;
+    let link = select_emergency_contacts(chat_id)
+        .filter(secondary_owners::Column::Id.eq(link_id))
+        .one(connection)
+        .await?;
+    let link = match link {
+        Some(link) => link,
+        None => {
+            bot.send_message(chat_id, Text::ContactNotFound.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+
+    let contact = get_contact_name(connection, &link, language).await;
+    let mut answer = format!(
+        "{}\n\n{}",
+        Text::ContactSince.format(
+            language,
+            &[
+                ("contact", &contact),
+                ("since", &link.created_at.format("%d.%m.%Y").to_string()),
+            ],
+        ),
+        Text::ContactTierHint.get(language)
+    );
+    if link.channel != ContactChannel::Telegram {
+        answer = format!("{}\n{}", answer, Text::ExternalContactHint.get(language));
+    }
+    bot.send_message(chat_id, answer)
+        .reply_markup(get_contact_keyboard(&link, language))
+        .await?;
+
+    Ok(None)
+}
+
+pub async fn ask_for_external_contact(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let language = get_language(connection, chat_id).await;
+    if !notifiers.supports(ContactChannel::Email) && !notifiers.supports(ContactChannel::Sms) {
+        bot.send_message(chat_id, Text::ExternalContactsUnavailable.get(language))
+            .await?;
+        return Ok(None);
+    }
+
+    bot.send_message(chat_id, Text::AskForExternalContact.get(language))
+        .await?;
+    Ok(Some(BotDialogState::WaitingForExternalContact))
+}
+
+pub async fn handle_add_external_contact(
+    bot: &Bot,
+    me: &Me,
+    message: &Message,
+    connection: &DatabaseConnection,
+    notifiers: &Notifiers,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let chat_id = message.chat.id;
+    let language = get_language(connection, chat_id).await;
+
+    let (channel, address) = match parse_contact_address(message.text().unwrap_or("")) {
+        Some(contact) => contact,
+        None => {
+            bot.send_message(chat_id, Text::InvalidContactAddress.get(language))
+                .await?;
+            return Ok(Some(BotDialogState::WaitingForExternalContact));
+        }
+    };
+    if !notifiers.supports(channel) {
+        let answer = match channel {
+            ContactChannel::Email => Text::EmailUnavailable,
+            _ => Text::SmsUnavailable,
+        };
+        bot.send_message(chat_id, answer.get(language)).await?;
+        return Ok(Some(BotDialogState::WaitingForExternalContact));
+    }
+
+    let answer = if add_external_contact(connection, chat_id, channel, &address).await? {
+        Text::ExternalContactAdded
+    } else {
+        Text::ExternalContactExists
+    };
+    bot.send_message(chat_id, answer.format(language, &[("contact", &address)]))
+        .await?;
+
+    show_owner_menu(bot, me, chat_id, connection, tera).await
+}
+
+pub async fn handle_set_contact_tier(
+    bot: &Bot,
+    chat_id: ChatId,
+    link_id: i32,
+    tier: i32,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let tier = match ContactTier::try_from_value(&tier) {
+        Ok(tier) => tier,
+        Err(_) => return Err(format!("Unknown contact tier: {}", tier).into()),
+    };
+    set_contact_tier(connection, chat_id, link_id, tier).await?;
+    show_emergency_contact(bot, chat_id, link_id, connection).await
+}
+
+pub async fn handle_remove_emergency_contact(
+    bot: &Bot,
+    me: &Me,
+    chat_id: ChatId,
+    link_id: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let link = select_emergency_contacts(chat_id)
+        .filter(secondary_owners::Column::Id.eq(link_id))
+        .one(connection)
+        .await?;
+
+    let unlinked = match &link {
+        Some(link) => unlink(bot, chat_id, link, connection).await?,
+        None => false,
+    };
+    if !unlinked {
+        let language = get_language(connection, chat_id).await;
+        bot.send_message(chat_id, Text::ContactNotFound.get(language))
+            .await?;
+    }
+
+    show_owner_menu(bot, me, chat_id, connection, tera).await
+}
+
+pub async fn ask_to_leave_owner(
+    bot: &Bot,
+    chat_id: ChatId,
+    link_id: i32,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let language = get_language(connection, chat_id).await;
+    let link = select_backed_up_owners(chat_id)
+        .filter(secondary_owners::Column::Id.eq(link_id))
+        .one(connection)
+        .await?;
+    let link = match link {
+        Some(link) => link,
+        None => {
+            bot.send_message(chat_id, Text::ContactNotFound.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+
+    let username = get_display_name(connection, ChatId(link.primary_owner_chat_id), language).await;
+    bot.send_message(
+        chat_id,
+        Text::ConfirmLeaveOwner.format(language, &[("username", &username)]),
+    )
+    .reply_markup(get_leave_owner_keyboard(link.id, language))
+    .await?;
+
+    Ok(None)
+}
+
+pub async fn handle_leave_owner(
+    bot: &Bot,
+    chat_id: ChatId,
+    link_id: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let link = select_backed_up_owners(chat_id)
+        .filter(secondary_owners::Column::Id.eq(link_id))
+        .one(connection)
+        .await?;
+
+    let unlinked = match &link {
+        Some(link) => unlink(bot, chat_id, link, connection).await?,
+        None => false,
+    };
+    if !unlinked {
+        let language = get_language(connection, chat_id).await;
+        bot.send_message(chat_id, Text::ContactNotFound.get(language))
+            .await?;
+    }
+
+    show_contact_menu(bot, chat_id, connection, tera).await
+}
@@ -1,55 +1,207 @@
 use sea_orm::prelude::*;
-use sea_orm::{sea_query::OnConflict, ActiveValue};
+use serde_json::json;
 use std::error::Error;
 use teloxide::{
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile, ParseMode},
 };
 use tera::{Context, Tera};
 
-use crate::{entity::emergency_info, types::BotDialogState};
+use crate::{
+    crypto::Keyring,
+    emergency_info::utils::{
+        add_attachment, get_attachments, get_next_section, get_previous_section, get_section_index,
+        get_sections, remove_attachment, set_section, Attachment, EMERGENCY_SECTIONS,
+    },
+    entity::{
+        emergency_attachments::AttachmentKind, emergency_info::EmergencySection, profiles::Language,
+    },
+    i18n::{render, Text},
+    profiles::utils::get_language,
+    types::BotDialogState,
+};
+
+fn get_section_title(section: EmergencySection) -> Text {
+    match section {
+        EmergencySection::HomeAccess => Text::EmergencyHomeAccess,
+        EmergencySection::Documents => Text::EmergencyDocuments,
+        EmergencySection::Health => Text::EmergencyHealth,
+        EmergencySection::Diet => Text::EmergencyDiet,
+        EmergencySection::Instructions => Text::EmergencyInstructions,
+        EmergencySection::Vet => Text::EmergencyVet,
+    }
+}
+
+fn get_section_prompt(section: EmergencySection) -> Text {
+    match section {
+        EmergencySection::HomeAccess => Text::AskForEmergencyHomeAccess,
+        EmergencySection::Documents => Text::AskForEmergencyDocuments,
+        EmergencySection::Health => Text::AskForEmergencyHealth,
+        EmergencySection::Diet => Text::AskForEmergencyDiet,
+        EmergencySection::Instructions => Text::AskForEmergencyInstructions,
+        EmergencySection::Vet => Text::AskForEmergencyVet,
+    }
+}
+
+// Sections as headed blocks for the templates, titles in the reader's language
+pub fn format_sections(
+    sections: &[(EmergencySection, String)],
+    language: Language,
+) -> Vec<serde_json::Value> {
+    sections
+        .iter()
+        .map(|(section, text)| {
+            json!({
+                "title": get_section_title(*section).get(language),
+                "text": text,
+            })
+        })
+        .collect()
+}
+
+fn get_attachment_label(attachment: &Attachment, language: Language) -> String {
+    let kind = match attachment.kind {
+        AttachmentKind::Photo => Text::AttachmentPhoto,
+        AttachmentKind::Document => Text::AttachmentDocument,
+        AttachmentKind::Location => Text::AttachmentLocation,
+    };
+    match &attachment.caption {
+        Some(caption) => format!(
+            "{}: {}",
+            kind.get(language),
+            caption.chars().take(24).collect::<String>()
+        ),
+        None => kind.get(language).to_string(),
+    }
+}
 
-async fn get_emerengecy_info_keyboard() -> InlineKeyboardMarkup {
+pub async fn send_attachments(
+    bot: &Bot,
+    chat_id: ChatId,
+    attachments: &[Attachment],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for attachment in attachments {
+        let caption = attachment.caption.clone().unwrap_or_default();
+        match (attachment.kind, &attachment.file_id) {
+            (AttachmentKind::Photo, Some(file_id)) => {
+                bot.send_photo(chat_id, InputFile::file_id(file_id))
+                    .caption(caption)
+                    .await?;
+            }
+            (AttachmentKind::Document, Some(file_id)) => {
+                bot.send_document(chat_id, InputFile::file_id(file_id))
+                    .caption(caption)
+                    .await?;
+            }
+            (AttachmentKind::Location, _) => {
+                if let (Some(latitude), Some(longitude)) =
+                    (attachment.latitude, attachment.longitude)
+                {
+                    bot.send_location(chat_id, latitude, longitude).await?;
+                }
+            }
+            _ => log::warn!("Skipping incomplete attachment {:?}", attachment),
+        }
+    }
+
+    Ok(())
+}
+
+fn get_emerengecy_info_keyboard(
+    attachments: &[Attachment],
+    language: Language,
+) -> InlineKeyboardMarkup {
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "👈 Меню для владельцев питомцев",
+        Text::OwnerMenuButton.get(language),
         "/owner_menu",
     )]);
 
+    for sections in EMERGENCY_SECTIONS.chunks(2) {
+        keyboard.push(
+            sections
+                .iter()
+                .map(|section| {
+                    InlineKeyboardButton::callback(
+                        get_section_title(*section).get(language),
+                        format!("/edit_emergency_section {}", section.to_value()),
+                    )
+                })
+                .collect(),
+        );
+    }
+
+    for attachment in attachments {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            format!("🗑 {}", get_attachment_label(attachment, language)),
+            format!("/remove_attachment {}", attachment.id),
+        )]);
+    }
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::AddAttachmentButton.get(language),
+        "/ask_for_attachment",
+    )]);
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "✍️ Задать экстренную информацию",
+        Text::FillEmergencyInfoButton.get(language),
         "/ask_for_emergency_info",
     )]);
 
     InlineKeyboardMarkup::new(keyboard)
 }
 
+fn get_section_keyboard(
+    section: EmergencySection,
+    wizard: bool,
+    language: Language,
+) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    if !wizard {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            Text::BackButton.get(language),
+            "/emergency_info",
+        )]);
+        return InlineKeyboardMarkup::new(keyboard);
+    }
+
+    let mut row = vec![];
+    if let Some(previous) = get_previous_section(section) {
+        row.push(InlineKeyboardButton::callback(
+            Text::BackButton.get(language),
+            format!("/emergency_wizard {}", previous.to_value()),
+        ));
+    }
+    row.push(InlineKeyboardButton::callback(
+        Text::SkipButton.get(language),
+        match get_next_section(section) {
+            Some(next) => format!("/emergency_wizard {}", next.to_value()),
+            None => "/emergency_info".to_string(),
+        },
+    ));
+    keyboard.push(row);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
 pub async fn show_emergency_info(
     bot: &Bot,
     chat_id: ChatId,
     connection: &DatabaseConnection,
+    keyring: &Keyring,
     tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
-    let emergency_text = emergency_info::Entity::find()
-        .filter(emergency_info::Column::ChatId.eq(chat_id.0))
-        .one(connection)
-        .await
-        .ok()
-        .flatten()
-        .map(|x| x.text);
-
-    let context = match emergency_text {
-        Some(emergency_text) => {
-            let mut context = Context::new();
-            context.insert("emergency_text", &emergency_text);
-            context
-        }
-        None => Context::new(),
-    };
+    let language = get_language(connection, chat_id).await;
+    let sections = get_sections(connection, keyring, chat_id).await?;
+    let attachments = get_attachments(connection, keyring, chat_id).await?;
+    send_attachments(bot, chat_id, &attachments).await?;
 
-    let answer = tera.render("emergency_info.html", &context).unwrap();
-    let keyboard = get_emerengecy_info_keyboard().await;
+    let mut context = Context::new();
+    context.insert("sections", &format_sections(&sections, language));
+    context.insert("attachments_count", &attachments.len());
+    let answer = render(tera, language, "emergency_info.html", &context).unwrap();
+    let keyboard = get_emerengecy_info_keyboard(&attachments, language);
     bot.send_message(chat_id, answer)
         .parse_mode(ParseMode::Html)
         .reply_markup(keyboard)
@@ -58,36 +210,154 @@ pub async fn show_emergency_info(
     Ok(None)
 }
 
+pub async fn ask_for_emergency_section(
+    bot: &Bot,
+    chat_id: ChatId,
+    section: EmergencySection,
+    wizard: bool,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let title = get_section_title(section).get(language);
+    let title = if wizard {
+        Text::EmergencyStep.format(
+            language,
+            &[
+                ("step", &(get_section_index(section) + 1).to_string()),
+                ("total", &EMERGENCY_SECTIONS.len().to_string()),
+                ("title", title),
+            ],
+        )
+    } else {
+        title.to_string()
+    };
+    let answer = format!(
+        "{}\n\n{}\n{}",
+        title,
+        get_section_prompt(section).get(language),
+        Text::ClearSectionHint.get(language)
+    );
+
+    bot.send_message(chat_id, answer)
+        .reply_markup(get_section_keyboard(section, wizard, language))
+        .await?;
+
+    Ok(Some(BotDialogState::WaitingEmergencySection {
+        section,
+        wizard,
+    }))
+}
+
 pub async fn ask_for_emergency_info(
     bot: &Bot,
     chat_id: ChatId,
+    connection: &DatabaseConnection,
     tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
     let context = tera::Context::new();
-    let answer = tera.render("emergency_info_fill.html", &context).unwrap();
+    let answer = render(tera, language, "emergency_info_fill.html", &context).unwrap();
     bot.send_message(chat_id, answer)
         .parse_mode(ParseMode::Html)
         .await?;
-    Ok(Some(BotDialogState::WaitingEmergencyText))
+
+    ask_for_emergency_section(bot, chat_id, EMERGENCY_SECTIONS[0], true, connection).await
 }
 
-pub async fn set_emergency_info(
+pub async fn handle_set_emergency_section(
+    bot: &Bot,
     message: &Message,
+    section: EmergencySection,
+    wizard: bool,
     connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
-    let new_emergency_info = emergency_info::ActiveModel {
-        text: ActiveValue::Set(message.text().unwrap_or("").to_string()),
-        chat_id: ActiveValue::Set(message.chat.id.0),
-        ..Default::default()
-    };
-    emergency_info::Entity::insert(new_emergency_info)
-        .on_conflict(
-            OnConflict::column(emergency_info::Column::ChatId)
-                .update_column(emergency_info::Column::Text)
-                .to_owned(),
-        )
-        .exec(connection)
+    let chat_id = message.chat.id;
+    let language = get_language(connection, chat_id).await;
+
+    // Files and locations can come at any step, the section stays open for the text
+    if message.text().is_none()
+        && add_attachment(connection, keyring, message)
+            .await?
+            .is_some()
+    {
+        bot.send_message(chat_id, Text::AttachmentSaved.get(language))
+            .await?;
+        return Ok(Some(BotDialogState::WaitingEmergencySection {
+            section,
+            wizard,
+        }));
+    }
+
+    let text = message.text().map(|x| x.trim()).unwrap_or("");
+    if text.is_empty() {
+        bot.send_message(chat_id, Text::ExpectedText.get(language))
+            .await?;
+        return Ok(Some(BotDialogState::WaitingEmergencySection {
+            section,
+            wizard,
+        }));
+    }
+
+    // "-" clears the section
+    let text = Some(text.to_string()).filter(|x| x != "-");
+    set_section(connection, keyring, chat_id, section, text).await?;
+
+    match get_next_section(section) {
+        Some(next) if wizard => {
+            ask_for_emergency_section(bot, chat_id, next, true, connection).await
+        }
+        _ => show_emergency_info(bot, chat_id, connection, keyring, tera).await,
+    }
+}
+
+pub async fn ask_for_attachment(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let keyboard = vec![vec![InlineKeyboardButton::callback(
+        Text::BackButton.get(language),
+        "/emergency_info",
+    )]];
+
+    bot.send_message(chat_id, Text::AskForAttachment.get(language))
+        .reply_markup(InlineKeyboardMarkup::new(keyboard))
         .await?;
 
-    Ok(None)
+    Ok(Some(BotDialogState::WaitingEmergencyAttachment))
+}
+
+pub async fn handle_add_attachment(
+    bot: &Bot,
+    message: &Message,
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    if add_attachment(connection, keyring, message)
+        .await?
+        .is_none()
+    {
+        let language = get_language(connection, message.chat.id).await;
+        bot.send_message(message.chat.id, Text::ExpectedAttachment.get(language))
+            .await?;
+        return Ok(Some(BotDialogState::WaitingEmergencyAttachment));
+    }
+
+    show_emergency_info(bot, message.chat.id, connection, keyring, tera).await
+}
+
+pub async fn handle_remove_attachment(
+    bot: &Bot,
+    chat_id: ChatId,
+    attachment_id: i32,
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    remove_attachment(connection, chat_id, attachment_id).await?;
+    show_emergency_info(bot, chat_id, connection, keyring, tera).await
 }
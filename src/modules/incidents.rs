@@ -0,0 +1,169 @@
+use sea_orm::{prelude::*, TransactionTrait};
+use std::error::Error;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+};
+use tera::{Context, Tera};
+
+use crate::{
+    entity::{incidents, profiles::Language},
+    i18n::{render, Text},
+    incidents::utils::{acknowledge_incident, resolve_incident, select_notified_contacts},
+    notifiers::Notification,
+    outbox::utils::{enqueue_message, enqueue_notice},
+    profiles::utils::{get_contact_language, get_display_name, get_language},
+    types::BotDialogState,
+};
+
+pub fn get_incident_keyboard(incident_id: i32, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::AcknowledgeButton.get(language),
+        format!("/acknowledge_incident {}", incident_id),
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+// Tells the alerted contacts to stand down if the owner had an open incident
+pub async fn resolve_incident_and_notify<C: ConnectionTrait>(
+    chat_id: ChatId,
+    connection: &C,
+    tera: &Tera,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let incident = match resolve_incident(connection, chat_id).await? {
+        Some(incident) => incident,
+        None => return Ok(()),
+    };
+    log::info!("Resolved {:?}", incident);
+
+    let recipients = select_notified_contacts(&incident).all(connection).await?;
+    for recipient in recipients {
+        let language = get_contact_language(connection, &recipient).await;
+        let username = get_display_name(connection, chat_id, language).await;
+        let mut context = Context::new();
+        context.insert("username", &username);
+        let message = render(tera, language, "all_clear.html", &context).unwrap();
+
+        log::info!("Notifying {:?}", recipient);
+        let notification = Notification {
+            subject: Text::NotificationSubject.format(language, &[("username", &username)]),
+            html: message,
+            ..Default::default()
+        };
+        enqueue_notice(connection, &recipient, &notification).await?;
+    }
+
+    Ok(())
+}
+
+async fn render_acknowledged<C: ConnectionTrait>(
+    tera: &Tera,
+    language: Language,
+    username: &str,
+    contact_chat_id: ChatId,
+    connection: &C,
+) -> String {
+    let mut context = Context::new();
+    context.insert("username", username);
+    context.insert(
+        "contact",
+        &get_display_name(connection, contact_chat_id, language).await,
+    );
+    render(tera, language, "incident_acknowledged.html", &context).unwrap()
+}
+
+pub async fn handle_acknowledge_incident(
+    bot: &Bot,
+    chat_id: ChatId,
+    incident_id: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let incident = match incidents::Entity::find_by_id(incident_id)
+        .one(connection)
+        .await?
+    {
+        Some(incident) => incident,
+        None => return Err("Unknown incident".into()),
+    };
+    let owner_chat_id = ChatId(incident.chat_id);
+
+    let language = get_language(connection, chat_id).await;
+    // Later tiers never heard of the incident, so only the alerted contacts
+    // can acknowledge it and get told about the acknowledgement
+    let recipients = select_notified_contacts(&incident).all(connection).await?;
+    if !recipients
+        .iter()
+        .any(|x| x.secondary_owner_chat_id == Some(chat_id.0))
+    {
+        bot.send_message(chat_id, Text::IncidentNotAlerted.get(language))
+            .await?;
+        return Ok(None);
+    }
+
+    if incident.resolved_at.is_some() {
+        bot.send_message(chat_id, Text::IncidentResolved.get(language))
+            .await?;
+        return Ok(None);
+    }
+
+    let transaction = connection.begin().await?;
+    if !acknowledge_incident(&transaction, incident_id, chat_id).await? {
+        transaction.rollback().await?;
+        let acknowledged_by = incidents::Entity::find_by_id(incident_id)
+            .one(connection)
+            .await?
+            .and_then(|x| x.acknowledged_by);
+        let answer = match acknowledged_by {
+            Some(acknowledged_by) if acknowledged_by == chat_id.0 => {
+                Text::AlreadyAcknowledgedByYou.get(language).to_string()
+            }
+            Some(acknowledged_by) => Text::AlreadyAcknowledgedBy.format(
+                language,
+                &[(
+                    "contact",
+                    &get_display_name(connection, ChatId(acknowledged_by), language).await,
+                )],
+            ),
+            None => Text::IncidentResolved.get(language).to_string(),
+        };
+        bot.send_message(chat_id, answer).await?;
+        return Ok(None);
+    }
+
+    for recipient in recipients
+        .iter()
+        .filter(|x| x.secondary_owner_chat_id != Some(chat_id.0))
+    {
+        let language = get_contact_language(connection, recipient).await;
+        let username = get_display_name(connection, owner_chat_id, language).await;
+        let notification = Notification {
+            subject: Text::NotificationSubject.format(language, &[("username", &username)]),
+            html: render_acknowledged(tera, language, &username, chat_id, connection).await,
+            ..Default::default()
+        };
+
+        log::info!("Notifying {:?}", recipient);
+        enqueue_notice(&transaction, recipient, &notification).await?;
+    }
+
+    let owner_language = get_language(connection, owner_chat_id).await;
+    let username = get_display_name(connection, owner_chat_id, owner_language).await;
+    let message = render_acknowledged(tera, owner_language, &username, chat_id, connection).await;
+    enqueue_message(
+        &transaction,
+        owner_chat_id,
+        message,
+        Some(ParseMode::Html),
+        None,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    bot.send_message(chat_id, Text::AcknowledgeThanks.get(language))
+        .await?;
+    Ok(None)
+}
@@ -1,51 +1,200 @@
-use sea_orm::{prelude::*, ActiveValue};
+use chrono::Utc;
+use sea_orm::{prelude::*, TransactionTrait};
 use std::error::Error;
-use teloxide::prelude::*;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
 
 use crate::{
-    entity::{invites, secondary_owners},
+    entity::profiles::Language,
+    i18n::Text,
+    invites::utils::{
+        approve_contact_request, count_pending_contact_requests, create_contact_request,
+        get_invite_status, lock_invite, reject_contact_request, select_contact_request,
+        select_invite_by_code, ContactRequestApproval, InviteStatus, INVITE_MAX_PENDING_REQUESTS,
+    },
+    outbox::utils::enqueue_message,
+    profiles::utils::{get_display_name, get_language, is_emergency_contact},
     types::BotDialogState,
 };
 
 pub async fn ask_for_invite(
     bot: &Bot,
     chat_id: ChatId,
+    connection: &DatabaseConnection,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
-    bot.send_message(
-        chat_id,
-        "Пожалуйста отправьте код приглашения следующим сообщением.",
-    )
-    .await?;
+    let language = get_language(connection, chat_id).await;
+    bot.send_message(chat_id, Text::AskForInvite.get(language))
+        .await?;
     Ok(Some(BotDialogState::WaitingForInvite))
 }
 
+// The code comes either as a message or as a `/start` deep link payload
 pub async fn accept_invite(
     bot: &Bot,
-    message: &Message,
+    chat_id: ChatId,
+    invite_code: &str,
     connection: &DatabaseConnection,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
-    let invite_code = message.text().unwrap_or("").to_string();
-    let invite = invites::Entity::find()
-        .filter(invites::Column::Invite.eq(invite_code))
-        .one(connection)
-        .await
-        .ok()
-        .flatten();
+    let language = get_language(connection, chat_id).await;
+    let invite_code = invite_code.trim();
+    let invite = match select_invite_by_code(invite_code).one(connection).await? {
+        Some(invite) => invite,
+        None => {
+            bot.send_message(chat_id, Text::UnknownInvite.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+
+    let rejection = match get_invite_status(&invite, Utc::now().naive_utc()) {
+        InviteStatus::Active => None,
+        InviteStatus::Expired => Some(Text::ExpiredInvite),
+        InviteStatus::Used => Some(Text::UsedInvite),
+        InviteStatus::Revoked => Some(Text::RevokedInvite),
+    };
+    if let Some(rejection) = rejection {
+        bot.send_message(chat_id, rejection.get(language)).await?;
+        return Ok(None);
+    }
 
-    if invite.is_none() {
-        bot.send_message(message.chat.id, "Неизвестный код приглашения.")
+    let owner_chat_id = ChatId(invite.chat_id);
+    if owner_chat_id == chat_id {
+        bot.send_message(chat_id, Text::SelfInvite.get(language))
             .await?;
         return Ok(None);
     }
-    let invite = invite.unwrap();
-
-    secondary_owners::Entity::insert(secondary_owners::ActiveModel {
-        primary_owner_chat_id: ActiveValue::Set(invite.chat_id),
-        secondary_owner_chat_id: ActiveValue::Set(message.chat.id.0),
-        ..Default::default()
-    })
-    .exec(connection)
+    if is_emergency_contact(connection, owner_chat_id, chat_id).await? {
+        let username = get_display_name(connection, owner_chat_id, language).await;
+        bot.send_message(
+            chat_id,
+            Text::AlreadyLinked.format(language, &[("username", &username)]),
+        )
+        .await?;
+        return Ok(None);
+    }
+
+    // The owner may have replaced the code since it was loaded
+    let transaction = connection.begin().await?;
+    let invite = match lock_invite(&transaction, &invite).await? {
+        Some(invite) => invite,
+        None => {
+            transaction.rollback().await?;
+            bot.send_message(chat_id, Text::UnknownInvite.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+    let pending = count_pending_contact_requests(&transaction, &invite, chat_id).await?;
+    if pending >= INVITE_MAX_PENDING_REQUESTS {
+        transaction.rollback().await?;
+        bot.send_message(chat_id, Text::TooManyContactRequests.get(language))
+            .await?;
+        return Ok(None);
+    }
+
+    // The contact is linked and the code is used only once the owner approves
+    let request = create_contact_request(&transaction, &invite, chat_id).await?;
+
+    let owner_language = get_language(connection, owner_chat_id).await;
+    let contact = get_display_name(connection, chat_id, owner_language).await;
+    enqueue_message(
+        &transaction,
+        owner_chat_id,
+        Text::ContactRequest.format(owner_language, &[("contact", &contact)]),
+        None,
+        Some(get_contact_request_keyboard(request.id, owner_language)),
+    )
+    .await?;
+    transaction.commit().await?;
+
+    let username = get_display_name(connection, owner_chat_id, language).await;
+    bot.send_message(
+        chat_id,
+        Text::ContactRequestSent.format(language, &[("username", &username)]),
+    )
     .await?;
 
     Ok(None)
 }
+
+fn get_contact_request_keyboard(request_id: i32, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![
+        InlineKeyboardButton::callback(
+            Text::ApproveContactButton.get(language),
+            format!("/approve_contact {}", request_id),
+        ),
+        InlineKeyboardButton::callback(
+            Text::RejectContactButton.get(language),
+            format!("/reject_contact {}", request_id),
+        ),
+    ]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub async fn handle_contact_request(
+    bot: &Bot,
+    chat_id: ChatId,
+    request_id: i32,
+    approve: bool,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
+    let language = get_language(connection, chat_id).await;
+    let request = select_contact_request(chat_id, request_id)
+        .one(connection)
+        .await?;
+    let transaction = connection.begin().await?;
+    let failure = match &request {
+        Some(request) if approve => match approve_contact_request(&transaction, request).await? {
+            ContactRequestApproval::Approved => None,
+            ContactRequestApproval::AlreadyHandled => Some(Text::ContactRequestNotFound),
+            ContactRequestApproval::InviteUnavailable => {
+                Some(Text::ContactRequestInviteUnavailable)
+            }
+        },
+        Some(request) if reject_contact_request(&transaction, request).await? => None,
+        _ => Some(Text::ContactRequestNotFound),
+    };
+    let request = match (request, failure) {
+        (Some(request), None) => request,
+        (_, failure) => {
+            transaction.rollback().await?;
+            let failure = failure.unwrap_or(Text::ContactRequestNotFound);
+            bot.send_message(chat_id, failure.get(language)).await?;
+            return Ok(None);
+        }
+    };
+
+    let contact_chat_id = ChatId(request.secondary_owner_chat_id);
+    let contact_language = get_language(connection, contact_chat_id).await;
+    let username = get_display_name(connection, chat_id, contact_language).await;
+    let answer = if approve {
+        Text::InviteAccepted
+    } else {
+        Text::InviteRejected
+    };
+    enqueue_message(
+        &transaction,
+        contact_chat_id,
+        answer.format(contact_language, &[("username", &username)]),
+        None,
+        None,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    let contact = get_display_name(connection, contact_chat_id, language).await;
+    let answer = if approve {
+        Text::ContactApproved
+    } else {
+        Text::ContactRejected
+    };
+    bot.send_message(chat_id, answer.format(language, &[("contact", &contact)]))
+        .await?;
+
+    Ok(None)
+}
@@ -0,0 +1,66 @@
+use sea_orm::{prelude::*, ActiveEnum};
+use std::error::Error;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+};
+
+use crate::{
+    entity::profiles::Language,
+    i18n::{Text, LANGUAGES},
+    profiles::utils::{get_language, parse_language, set_language},
+    types::BotDialogState,
+};
+
+fn get_keyboard(current: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(
+        LANGUAGES
+            .iter()
+            .map(|(language, label)| {
+                let label = if *language == current {
+                    format!("✅ {}", label)
+                } else {
+                    label.to_string()
+                };
+                InlineKeyboardButton::callback(
+                    label,
+                    format!("/set_language {}", language.to_value()),
+                )
+            })
+            .collect(),
+    );
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub async fn show_language_menu(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    bot.send_message(chat_id, Text::ChooseLanguage.get(language))
+        .reply_markup(get_keyboard(language))
+        .await?;
+
+    Ok(None)
+}
+
+pub async fn handle_set_language(
+    bot: &Bot,
+    chat_id: ChatId,
+    code: &str,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = match parse_language(code) {
+        Some(language) => language,
+        None => return Err(format!("Unsupported language: {}", code).into()),
+    };
+    set_language(connection, chat_id, language).await?;
+    bot.send_message(chat_id, Text::LanguageChanged.get(language))
+        .await?;
+
+    Ok(None)
+}
@@ -1,6 +1,11 @@
 pub mod alive;
 pub mod contact_menu;
+pub mod contacts;
 pub mod emergency_info;
+pub mod incidents;
 pub mod invites;
+pub mod language;
 pub mod owner_menu;
+pub mod pets;
+pub mod settings;
 pub mod start;
@@ -1,132 +1,239 @@
-use rand::{distributions::Alphanumeric, Rng};
-use sea_orm::{prelude::*, ActiveValue, JoinType, QuerySelect};
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use sea_orm::{prelude::*, TransactionTrait};
 use std::error::Error;
 use teloxide::{
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile, Me, ParseMode},
 };
 use tera::{Context, Tera};
 
 use crate::{
-    entity::{invites, profiles, secondary_owners},
-    statuses::utils::set_monitoring,
+    entity::{alive_events, profiles::Language, prompt_events},
+    escalations::utils::get_next_alert_at,
+    health::utils::get_health_problems,
+    i18n::{render, Text},
+    invites::utils::{
+        get_invite_link, get_invite_status, get_or_create_invite, regenerate_invite,
+        render_qr_code, revoke_invite, select_invite, InviteStatus,
+    },
+    profiles::utils::{
+        get_contact_name, get_language, get_time_zone, select_emergency_contacts, select_profile,
+    },
+    settings::utils::get_settings,
+    statuses::utils::{is_enabled, set_monitoring},
     types::BotDialogState,
 };
 
+use super::alive::check_in;
+use super::contacts::get_tier_label;
+
 pub async fn handle_enable_monitoring(
     bot: &Bot,
+    me: &Me,
     chat_id: ChatId,
     connection: &DatabaseConnection,
+    tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
-    set_monitoring(connection, chat_id, true).await?;
-    bot.send_message(chat_id, "Мониторинг включен.").await?;
-    Ok(None)
+    // Counts as a check-in, otherwise a prompt left from before the pause
+    // would fire the alert right away
+    let transaction = connection.begin().await?;
+    set_monitoring(&transaction, chat_id, true).await?;
+    check_in(chat_id, &transaction, tera).await?;
+    transaction.commit().await?;
+    show_owner_menu(bot, me, chat_id, connection, tera).await
 }
 
 pub async fn handle_disable_monitoring(
     bot: &Bot,
+    me: &Me,
     chat_id: ChatId,
     connection: &DatabaseConnection,
+    tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
     set_monitoring(connection, chat_id, false).await?;
-    bot.send_message(chat_id, "Мониторинг выключен.").await?;
-    Ok(None)
+    show_owner_menu(bot, me, chat_id, connection, tera).await
+}
+
+fn format_time(time: NaiveDateTime, time_zone: Tz) -> String {
+    time.and_utc()
+        .with_timezone(&time_zone)
+        .format("%d.%m %H:%M %Z")
+        .to_string()
 }
 
 async fn get_secondary_owners(
     connection: &DatabaseConnection,
     chat_id: ChatId,
-) -> Vec<profiles::Model> {
-    profiles::Entity::find()
-        .join_rev(
-            JoinType::InnerJoin,
-            secondary_owners::Entity::belongs_to(profiles::Entity)
-                .from(secondary_owners::Column::SecondaryOwnerChatId)
-                .to(profiles::Column::ChatId)
-                .into(),
-        )
-        .filter(secondary_owners::Column::PrimaryOwnerChatId.eq(chat_id.0))
+    language: Language,
+) -> Vec<String> {
+    let links = select_emergency_contacts(chat_id)
         .all(connection)
         .await
-        .unwrap_or(vec![])
+        .unwrap_or(vec![]);
+    let mut owners = vec![];
+    for link in links {
+        owners.push(get_contact_name(connection, &link, language).await);
+    }
+    owners
 }
 
-fn format_owners(owners: Vec<profiles::Model>) -> String {
-    let owners = owners
-        .iter()
-        .map(|profile| format!("@{}", profile.username.clone()))
-        .collect::<Vec<_>>();
-
+fn format_owners(owners: Vec<String>, language: Language) -> String {
     if owners.is_empty() {
-        "Нет контактов".to_string()
+        Text::NoContacts.get(language).to_string()
     } else {
         owners.join("\n")
     }
 }
 
-async fn get_invite_code(connection: &DatabaseConnection, chat_id: ChatId) -> Option<String> {
-    match invites::Entity::find()
-        .filter(invites::Column::ChatId.eq(chat_id.0))
-        .one(connection)
+async fn get_keyboard(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    invite_status: Option<InviteStatus>,
+    enabled: bool,
+    language: Language,
+) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![if enabled {
+        InlineKeyboardButton::callback(
+            Text::DisableMonitoringButton.get(language),
+            "/disable_monitoring",
+        )
+    } else {
+        InlineKeyboardButton::callback(
+            Text::EnableMonitoringButton.get(language),
+            "/enable_monitoring",
+        )
+    }]);
+
+    let links = select_emergency_contacts(chat_id)
+        .all(connection)
         .await
-        .ok()
-        .flatten()
-    {
-        Some(invite) => Some(invite.invite),
-        None => {
-            let invite_code = rand::thread_rng()
-                .sample_iter(&Alphanumeric)
-                .take(8)
-                .map(char::from)
-                .collect::<String>();
-
-            let invite = invites::ActiveModel {
-                chat_id: ActiveValue::Set(chat_id.0),
-                invite: ActiveValue::Set(invite_code.clone()),
-                ..Default::default()
-            };
-
-            match invite.insert(connection).await {
-                Ok(_) => Some(invite_code.clone()),
-                Err(_) => None,
-            }
-        }
+        .unwrap_or(vec![]);
+    for link in links {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            format!(
+                "👤 {} · {}",
+                get_contact_name(connection, &link, language).await,
+                get_tier_label(link.tier).get(language)
+            ),
+            format!("/contact {}", link.id),
+        )]);
     }
-}
-
-fn get_keyboard() -> InlineKeyboardMarkup {
-    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
 
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "👈 Меню резервного контакта",
+        Text::AddExternalContactButton.get(language),
+        "/ask_for_external_contact",
+    )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::ContactMenuButton.get(language),
         "/contact_menu",
     )]);
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "⚠️️ Экстренная информация",
+        Text::EmergencyInfoButton.get(language),
         "/emergency_info",
     )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::PetsButton.get(language),
+        "/pets",
+    )]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::MonitoringSettingsButton.get(language),
+        "/monitoring_settings",
+    )]);
+
+    let mut row = vec![InlineKeyboardButton::callback(
+        Text::RegenerateInviteButton.get(language),
+        "/regenerate_invite",
+    )];
+    if invite_status == Some(InviteStatus::Active) {
+        row.push(InlineKeyboardButton::callback(
+            Text::RevokeInviteButton.get(language),
+            "/revoke_invite",
+        ));
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            Text::InviteQrCodeButton.get(language),
+            "/invite_qr_code",
+        )]);
+    }
+    keyboard.push(row);
 
     InlineKeyboardMarkup::new(keyboard)
 }
 
 pub async fn show_owner_menu(
     bot: &Bot,
+    me: &Me,
     chat_id: ChatId,
     connection: &DatabaseConnection,
     tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
-    let secondary_owners = get_secondary_owners(connection, chat_id).await;
-    let secondary_owners = format_owners(secondary_owners);
+    let language = get_language(connection, chat_id).await;
+    let secondary_owners = get_secondary_owners(connection, chat_id, language).await;
+    let secondary_owners = format_owners(secondary_owners, language);
 
-    let invite_code = get_invite_code(connection, chat_id)
-        .await
-        .unwrap_or("Ошибка".to_string());
+    let invite = match get_or_create_invite(connection, chat_id).await {
+        Ok(invite) => Some(invite),
+        Err(err) => {
+            log::error!("Can't get invite for {}: {}", chat_id, err);
+            None
+        }
+    };
+    let invite_status = invite
+        .as_ref()
+        .map(|x| get_invite_status(x, Utc::now().naive_utc()));
 
-    let keyboard = get_keyboard();
+    let settings = get_settings(connection, chat_id).await;
+    let problems = get_health_problems(connection, chat_id).await?;
+    let enabled = is_enabled(connection, chat_id).await;
+    let time_zone = select_profile(chat_id)
+        .one(connection)
+        .await?
+        .map_or(Tz::UTC, |x| get_time_zone(&x));
+    let last_seen_at = alive_events::Entity::find()
+        .filter(alive_events::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await?
+        .map(|x| x.timestamp);
+    let prompted_at = prompt_events::Entity::find()
+        .filter(prompt_events::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await?
+        .map(|x| x.timestamp);
+
+    let keyboard = get_keyboard(connection, chat_id, invite_status, enabled, language).await;
     let mut context = Context::new();
+    context.insert("enabled", &enabled);
+    context.insert(
+        "last_seen_at",
+        &last_seen_at.map(|x| format_time(x, time_zone)),
+    );
+    if enabled {
+        let now = Utc::now().naive_utc();
+        let next_alert_at = get_next_alert_at(&settings, last_seen_at, prompted_at, now);
+        context.insert("next_alert_at", &format_time(next_alert_at, time_zone));
+    }
+    context.insert("check_in_interval_hours", &settings.check_in_interval_hours);
+    context.insert("alert_grace_hours", &settings.alert_grace_hours);
     context.insert("secondary_owners", &secondary_owners);
-    context.insert("invite_code", &invite_code);
-    let answer = tera.render("owner_menu.html", &context).unwrap();
+    context.insert("problems", &problems);
+    match &invite {
+        Some(invite) => {
+            context.insert("invite_code", &invite.invite);
+            context.insert(
+                "invite_link",
+                &get_invite_link(me.username(), &invite.invite),
+            );
+            context.insert(
+                "invite_expires_at",
+                &format_time(invite.expires_at, time_zone),
+            );
+        }
+        None => context.insert("invite_code", Text::InviteCodeError.get(language)),
+    }
+    context.insert("invite_status", &invite_status);
+    let answer = render(tera, language, "owner_menu.html", &context).unwrap();
     bot.send_message(chat_id, answer)
         .parse_mode(ParseMode::Html)
         .reply_markup(keyboard)
@@ -135,3 +242,51 @@ pub async fn show_owner_menu(
 
     Ok(None)
 }
+
+pub async fn handle_regenerate_invite(
+    bot: &Bot,
+    me: &Me,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    regenerate_invite(connection, chat_id).await?;
+    show_owner_menu(bot, me, chat_id, connection, tera).await
+}
+
+pub async fn handle_revoke_invite(
+    bot: &Bot,
+    me: &Me,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    revoke_invite(connection, chat_id).await?;
+    show_owner_menu(bot, me, chat_id, connection, tera).await
+}
+
+pub async fn send_invite_qr_code(
+    bot: &Bot,
+    me: &Me,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let invite = select_invite(chat_id).one(connection).await?;
+    let invite = match invite {
+        Some(invite)
+            if get_invite_status(&invite, Utc::now().naive_utc()) == InviteStatus::Active =>
+        {
+            invite
+        }
+        // The code changed since the menu was shown
+        _ => return show_owner_menu(bot, me, chat_id, connection, tera).await,
+    };
+
+    let link = get_invite_link(me.username(), &invite.invite);
+    bot.send_photo(chat_id, InputFile::memory(render_qr_code(&link)?))
+        .caption(link)
+        .await?;
+
+    Ok(None)
+}
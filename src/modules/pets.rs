@@ -0,0 +1,281 @@
+use sea_orm::prelude::*;
+use std::error::Error;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile, ParseMode},
+};
+use tera::{Context, Tera};
+
+use crate::{
+    crypto::Keyring,
+    entity::{pets, profiles::Language},
+    i18n::{render, Text},
+    pets::utils::{
+        add_pet, get_pet, get_pet_field_code, remove_pet, select_pet, select_pets, set_pet_field,
+    },
+    profiles::utils::get_language,
+    types::{BotDialogState, PetField},
+};
+
+fn get_pets_keyboard(pets: &[pets::Model], language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::OwnerMenuButton.get(language),
+        "/owner_menu",
+    )]);
+    for pet in pets {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            format!("🐾 {}", pet.name),
+            format!("/pet {}", pet.id),
+        )]);
+    }
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::AddPetButton.get(language),
+        "/add_pet",
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+fn get_edit_button(pet_id: i32, field: PetField, language: Language) -> InlineKeyboardButton {
+    let label = match field {
+        PetField::Name => Text::EditPetNameButton,
+        PetField::Species => Text::EditPetSpeciesButton,
+        PetField::Photo => Text::EditPetPhotoButton,
+        PetField::MedicalNotes => Text::EditPetMedicalNotesButton,
+        PetField::Diet => Text::EditPetDietButton,
+        PetField::VetContact => Text::EditPetVetContactButton,
+    };
+    InlineKeyboardButton::callback(
+        label.get(language),
+        format!("/edit_pet {} {}", pet_id, get_pet_field_code(field)),
+    )
+}
+
+fn get_pet_keyboard(pet_id: i32, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::BackToPetsButton.get(language),
+        "/pets",
+    )]);
+    keyboard.push(vec![
+        get_edit_button(pet_id, PetField::Name, language),
+        get_edit_button(pet_id, PetField::Species, language),
+        get_edit_button(pet_id, PetField::Photo, language),
+    ]);
+    keyboard.push(vec![
+        get_edit_button(pet_id, PetField::MedicalNotes, language),
+        get_edit_button(pet_id, PetField::Diet, language),
+        get_edit_button(pet_id, PetField::VetContact, language),
+    ]);
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::RemovePetButton.get(language),
+        format!("/remove_pet {}", pet_id),
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+fn get_remove_pet_keyboard(pet_id: i32, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![
+        InlineKeyboardButton::callback(
+            Text::ConfirmRemovePetButton.get(language),
+            format!("/confirm_remove_pet {}", pet_id),
+        ),
+        InlineKeyboardButton::callback(
+            Text::CancelButton.get(language),
+            format!("/pet {}", pet_id),
+        ),
+    ]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub async fn show_pets(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let pets = select_pets(chat_id).all(connection).await?;
+
+    let mut context = Context::new();
+    context.insert("has_pets", &!pets.is_empty());
+    let answer = render(tera, language, "pets.html", &context).unwrap();
+    bot.send_message(chat_id, answer)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(get_pets_keyboard(&pets, language))
+        .await?;
+
+    Ok(None)
+}
+
+pub async fn show_pet(
+    bot: &Bot,
+    chat_id: ChatId,
+    pet_id: i32,
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let pet = match get_pet(connection, keyring, chat_id, pet_id).await? {
+        Some(pet) => pet,
+        None => {
+            bot.send_message(chat_id, Text::PetNotFound.get(language))
+                .await?;
+            return show_pets(bot, chat_id, connection, tera).await;
+        }
+    };
+
+    if let Some(photo_file_id) = &pet.photo_file_id {
+        bot.send_photo(chat_id, InputFile::file_id(photo_file_id))
+            .await?;
+    }
+
+    let mut context = Context::new();
+    context.insert("pet", &pet);
+    let answer = render(tera, language, "pet.html", &context).unwrap();
+    bot.send_message(chat_id, answer)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(get_pet_keyboard(pet.id, language))
+        .await?;
+
+    Ok(None)
+}
+
+pub async fn ask_for_pet_name(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    bot.send_message(chat_id, Text::AskForPetName.get(language))
+        .await?;
+    Ok(Some(BotDialogState::WaitingForPetName))
+}
+
+pub async fn handle_add_pet(
+    bot: &Bot,
+    message: &Message,
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let name = message.text().unwrap_or("").trim();
+    if name.is_empty() {
+        let language = get_language(connection, message.chat.id).await;
+        bot.send_message(message.chat.id, Text::ExpectedText.get(language))
+            .await?;
+        return Ok(Some(BotDialogState::WaitingForPetName));
+    }
+
+    let pet = add_pet(connection, message.chat.id, name.to_string()).await?;
+    show_pet(bot, message.chat.id, pet.id, connection, keyring, tera).await
+}
+
+pub async fn ask_for_pet_field(
+    bot: &Bot,
+    chat_id: ChatId,
+    pet_id: i32,
+    field: PetField,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    if select_pet(chat_id, pet_id).one(connection).await?.is_none() {
+        bot.send_message(chat_id, Text::PetNotFound.get(language))
+            .await?;
+        return Ok(None);
+    }
+
+    let prompt = match field {
+        PetField::Name => Text::AskForPetName,
+        PetField::Species => Text::AskForPetSpecies,
+        PetField::Photo => Text::AskForPetPhoto,
+        PetField::MedicalNotes => Text::AskForPetMedicalNotes,
+        PetField::Diet => Text::AskForPetDiet,
+        PetField::VetContact => Text::AskForPetVetContact,
+    };
+    bot.send_message(chat_id, prompt.get(language)).await?;
+
+    Ok(Some(BotDialogState::WaitingForPetField { pet_id, field }))
+}
+
+pub async fn handle_set_pet_field(
+    bot: &Bot,
+    message: &Message,
+    pet_id: i32,
+    field: PetField,
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let chat_id = message.chat.id;
+    let text = message.text().map(|x| x.trim()).unwrap_or("");
+
+    let value = match field {
+        PetField::Photo => message
+            .photo()
+            .and_then(|sizes| sizes.last())
+            .map(|size| size.file.id.clone()),
+        _ => Some(text.to_string()).filter(|x| !x.is_empty()),
+    };
+    // "-" clears an optional field
+    let clear = text == "-" && field != PetField::Name;
+
+    if value.is_none() && !clear {
+        let language = get_language(connection, chat_id).await;
+        let answer = match field {
+            PetField::Photo => Text::ExpectedPetPhoto,
+            _ => Text::ExpectedText,
+        };
+        bot.send_message(chat_id, answer.get(language)).await?;
+        return Ok(Some(BotDialogState::WaitingForPetField { pet_id, field }));
+    }
+
+    let value = if clear { None } else { value };
+    set_pet_field(connection, keyring, chat_id, pet_id, field, value).await?;
+    show_pet(bot, chat_id, pet_id, connection, keyring, tera).await
+}
+
+pub async fn ask_to_remove_pet(
+    bot: &Bot,
+    chat_id: ChatId,
+    pet_id: i32,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let pet = match select_pet(chat_id, pet_id).one(connection).await? {
+        Some(pet) => pet,
+        None => {
+            bot.send_message(chat_id, Text::PetNotFound.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+
+    bot.send_message(
+        chat_id,
+        Text::ConfirmRemovePet.format(language, &[("name", &pet.name)]),
+    )
+    .reply_markup(get_remove_pet_keyboard(pet.id, language))
+    .await?;
+
+    Ok(None)
+}
+
+pub async fn handle_remove_pet(
+    bot: &Bot,
+    chat_id: ChatId,
+    pet_id: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    remove_pet(connection, chat_id, pet_id).await?;
+    show_pets(bot, chat_id, connection, tera).await
+}
@@ -0,0 +1,245 @@
+use sea_orm::prelude::*;
+use std::error::Error;
+use teloxide::{
+    prelude::*,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode},
+};
+use tera::{Context, Tera};
+
+use crate::{
+    entity::{profiles::Language, settings},
+    i18n::{render, Text},
+    profiles::utils::{get_language, parse_time_zone, select_profile, set_time_zone},
+    settings::utils::{
+        get_settings, set_alert_grace, set_check_in_interval, set_check_in_window, set_quiet_hours,
+        ALERT_GRACE_OPTIONS, CHECK_IN_INTERVAL_OPTIONS, CHECK_IN_WINDOW_OPTIONS,
+        QUIET_HOURS_OPTIONS,
+    },
+    types::BotDialogState,
+};
+
+fn format_option(hours: i32, current: i32, language: Language) -> String {
+    let label = Text::Hours.format(language, &[("hours", &hours.to_string())]);
+    if hours == current {
+        format!("✅ {}", label)
+    } else {
+        label
+    }
+}
+
+fn format_range(start: Option<i32>, end: Option<i32>) -> Option<String> {
+    match (start, end) {
+        (Some(start), Some(end)) => Some(format!("{:02}:00–{:02}:00", start, end)),
+        _ => None,
+    }
+}
+
+fn format_range_option(
+    range: (i32, i32),
+    current: (Option<i32>, Option<i32>),
+    empty: &str,
+) -> String {
+    let label = format_range(Some(range.0), Some(range.1))
+        .filter(|_| range.0 != range.1)
+        .unwrap_or(empty.to_string());
+    let is_current = match current {
+        (Some(start), Some(end)) => (start, end) == range,
+        _ => range.0 == range.1,
+    };
+
+    if is_current {
+        format!("✅ {}", label)
+    } else {
+        label
+    }
+}
+
+fn get_keyboard(settings: &settings::Model, language: Language) -> InlineKeyboardMarkup {
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
+
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::OwnerMenuButton.get(language),
+        "/owner_menu",
+    )]);
+    keyboard.push(
+        CHECK_IN_INTERVAL_OPTIONS
+            .iter()
+            .map(|hours| {
+                InlineKeyboardButton::callback(
+                    format_option(*hours, settings.check_in_interval_hours, language),
+                    format!("/set_check_in_interval {}", hours),
+                )
+            })
+            .collect(),
+    );
+    keyboard.push(
+        ALERT_GRACE_OPTIONS
+            .iter()
+            .map(|hours| {
+                InlineKeyboardButton::callback(
+                    format_option(*hours, settings.alert_grace_hours, language),
+                    format!("/set_alert_grace {}", hours),
+                )
+            })
+            .collect(),
+    );
+    keyboard.push(
+        CHECK_IN_WINDOW_OPTIONS
+            .iter()
+            .map(|range| {
+                InlineKeyboardButton::callback(
+                    format_range_option(
+                        *range,
+                        (settings.check_in_window_start, settings.check_in_window_end),
+                        Text::AnyTime.get(language),
+                    ),
+                    format!("/set_check_in_window {} {}", range.0, range.1),
+                )
+            })
+            .collect(),
+    );
+    keyboard.push(
+        QUIET_HOURS_OPTIONS
+            .iter()
+            .map(|range| {
+                InlineKeyboardButton::callback(
+                    format_range_option(
+                        *range,
+                        (settings.quiet_hours_start, settings.quiet_hours_end),
+                        Text::NoQuietHours.get(language),
+                    ),
+                    format!("/set_quiet_hours {} {}", range.0, range.1),
+                )
+            })
+            .collect(),
+    );
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        Text::TimeZoneButton.get(language),
+        "/ask_for_time_zone",
+    )]);
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub async fn show_monitoring_settings(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let settings = get_settings(connection, chat_id).await;
+    let profile = select_profile(chat_id).one(connection).await?;
+    let language = profile.as_ref().map_or(Language::default(), |x| x.language);
+    let time_zone = profile.map_or("UTC".to_string(), |x| x.time_zone);
+
+    let keyboard = get_keyboard(&settings, language);
+    let mut context = Context::new();
+    context.insert("check_in_interval_hours", &settings.check_in_interval_hours);
+    context.insert("alert_grace_hours", &settings.alert_grace_hours);
+    context.insert(
+        "check_in_window",
+        &format_range(settings.check_in_window_start, settings.check_in_window_end),
+    );
+    context.insert(
+        "quiet_hours",
+        &format_range(settings.quiet_hours_start, settings.quiet_hours_end),
+    );
+    context.insert("time_zone", &time_zone);
+    let answer = render(tera, language, "monitoring_settings.html", &context).unwrap();
+    bot.send_message(chat_id, answer)
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(None)
+}
+
+pub async fn handle_set_check_in_interval(
+    bot: &Bot,
+    chat_id: ChatId,
+    hours: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    if !CHECK_IN_INTERVAL_OPTIONS.contains(&hours) {
+        return Err(format!("Unsupported check-in interval: {}", hours).into());
+    }
+    set_check_in_interval(connection, chat_id, hours).await?;
+    show_monitoring_settings(bot, chat_id, connection, tera).await
+}
+
+pub async fn handle_set_alert_grace(
+    bot: &Bot,
+    chat_id: ChatId,
+    hours: i32,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    if !ALERT_GRACE_OPTIONS.contains(&hours) {
+        return Err(format!("Unsupported alert grace period: {}", hours).into());
+    }
+    set_alert_grace(connection, chat_id, hours).await?;
+    show_monitoring_settings(bot, chat_id, connection, tera).await
+}
+
+pub async fn handle_set_check_in_window(
+    bot: &Bot,
+    chat_id: ChatId,
+    range: (i32, i32),
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    if !CHECK_IN_WINDOW_OPTIONS.contains(&range) {
+        return Err(format!("Unsupported check-in window: {:?}", range).into());
+    }
+    set_check_in_window(connection, chat_id, range.0, range.1).await?;
+    show_monitoring_settings(bot, chat_id, connection, tera).await
+}
+
+pub async fn handle_set_quiet_hours(
+    bot: &Bot,
+    chat_id: ChatId,
+    range: (i32, i32),
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    if !QUIET_HOURS_OPTIONS.contains(&range) {
+        return Err(format!("Unsupported quiet hours: {:?}", range).into());
+    }
+    set_quiet_hours(connection, chat_id, range.0, range.1).await?;
+    show_monitoring_settings(bot, chat_id, connection, tera).await
+}
+
+pub async fn ask_for_time_zone(
+    bot: &Bot,
+    chat_id: ChatId,
+    connection: &DatabaseConnection,
+    tera: &Tera,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let language = get_language(connection, chat_id).await;
+    let context = Context::new();
+    let answer = render(tera, language, "time_zone_fill.html", &context).unwrap();
+    bot.send_message(chat_id, answer)
+        .parse_mode(ParseMode::Html)
+        .await?;
+    Ok(Some(BotDialogState::WaitingForTimeZone))
+}
+
+pub async fn handle_set_time_zone(
+    bot: &Bot,
+    message: &Message,
+    connection: &DatabaseConnection,
+) -> Result<Option<BotDialogState>, Box<dyn Error + Sync + Send>> {
+    let time_zone = match parse_time_zone(message.text().unwrap_or("")) {
+        Some(time_zone) => time_zone,
+        None => {
+            let language = get_language(connection, message.chat.id).await;
+            bot.send_message(message.chat.id, Text::UnknownTimeZone.get(language))
+                .await?;
+            return Ok(None);
+        }
+    };
+    set_time_zone(connection, message.chat.id, time_zone).await?;
+
+    Ok(None)
+}
@@ -4,38 +4,51 @@ use teloxide::prelude::*;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
 use tera::Tera;
 
+use crate::entity::profiles::Language;
+use crate::i18n::{render, Text};
+use crate::profiles::utils::get_language;
 use crate::statuses::utils::set_monitoring;
 use crate::types::BotDialogState;
 
 use super::alive::mark_alive;
-
-fn get_keyboard() -> InlineKeyboardMarkup {
-    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![];
-
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "🐶 Меню для владельцев питомцев",
-        "/owner_menu",
-    )]);
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "🛟 Меню для резервных контактов",
-        "/contact_menu",
-    )]);
+use super::contact_menu::show_contact_menu;
+use super::invites::accept_invite;
+
+fn get_keyboard(language: Language) -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![InlineKeyboardButton::callback(
+            Text::StartOwnerMenuButton.get(language),
+            "/owner_menu",
+        )],
+        vec![InlineKeyboardButton::callback(
+            Text::StartContactMenuButton.get(language),
+            "/contact_menu",
+        )],
+    ];
 
     InlineKeyboardMarkup::new(keyboard)
 }
 
+// `payload` is the invite code when the bot is opened via a `t.me/<bot>?start=<code>` link
 pub async fn show_start_info(
     bot: &Bot,
     message: &Message,
+    payload: &str,
     connection: &DatabaseConnection,
     tera: &Tera,
 ) -> Result<Option<BotDialogState>, Box<dyn Error + Send + Sync>> {
     mark_alive(connection, message.chat.id).await?;
     set_monitoring(connection, message.chat.id, true).await?;
 
-    let keyboard = get_keyboard();
+    if !payload.trim().is_empty() {
+        accept_invite(bot, message.chat.id, payload, connection).await?;
+        return show_contact_menu(bot, message.chat.id, connection, tera).await;
+    }
+
+    let language = get_language(connection, message.chat.id).await;
+    let keyboard = get_keyboard(language);
     let context = tera::Context::new();
-    let answer = tera.render("start.html", &context).unwrap();
+    let answer = render(tera, language, "start.html", &context).unwrap();
     bot.parse_mode(ParseMode::Html)
         .send_message(message.chat.id, answer)
         .reply_markup(keyboard)
@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::error::Error;
+
+use crate::config::SmtpConfig;
+
+use super::{html_to_text, Notification, Notifier, NotifyError};
+
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl EmailNotifier {
+    pub fn new(config: &SmtpConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(EmailNotifier {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::from_url(&config.url)?.build(),
+            from: config.from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, address: &str, notification: &Notification) -> Result<(), NotifyError> {
+        let to = match address.parse::<Mailbox>() {
+            Ok(to) => to,
+            Err(err) => return Err(NotifyError::Permanent(err.to_string())),
+        };
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&notification.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(html_to_text(&notification.html))
+            .map_err(|err| NotifyError::Permanent(err.to_string()))?;
+
+        match self.transport.send(message).await {
+            Ok(_) => Ok(()),
+            // 5xx replies, e.g. the mailbox doesn't exist
+            Err(err) if err.is_permanent() => Err(NotifyError::Permanent(err.to_string())),
+            Err(err) => Err(NotifyError::Transient(err.to_string())),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::InlineKeyboardMarkup;
+
+use crate::config::Config;
+use crate::emergency_info::utils::Attachment;
+use crate::entity::secondary_owners::{self, ContactChannel};
+
+pub mod email;
+pub mod sms;
+pub mod telegram;
+
+use email::EmailNotifier;
+use sms::SmsNotifier;
+use telegram::TelegramNotifier;
+
+// A message for a contact, every channel delivers as much of it as it can
+#[derive(Debug, Clone, Default)]
+pub struct Notification {
+    pub subject: String,
+    // Telegram HTML, email and SMS get it as plain text
+    pub html: String,
+    pub keyboard: Option<InlineKeyboardMarkup>,
+    pub attachments: Vec<Attachment>,
+    // Telegram file ids with their captions
+    pub photos: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub enum NotifyError {
+    // Worth another try later: network blips, rate limits, server errors
+    Transient(String),
+    // Retrying won't help: the address doesn't exist, the message is malformed
+    Permanent(String),
+    // The recipient's Telegram chat is gone: the bot is blocked or the account deleted
+    Unreachable(String),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Transient(message) => write!(f, "Transient error: {}", message),
+            NotifyError::Permanent(message) => write!(f, "Permanent error: {}", message),
+            NotifyError::Unreachable(message) => write!(f, "Unreachable: {}", message),
+        }
+    }
+}
+
+impl Error for NotifyError {}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    // `address` is a chat id, an email address or a phone number, depending on the channel
+    async fn notify(&self, address: &str, notification: &Notification) -> Result<(), NotifyError>;
+}
+
+#[derive(Clone)]
+pub struct Notifiers {
+    telegram: Arc<dyn Notifier>,
+    email: Option<Arc<dyn Notifier>>,
+    sms: Option<Arc<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new(bot: Bot, config: &Config) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let email: Option<Arc<dyn Notifier>> = match &config.smtp {
+            Some(smtp) => Some(Arc::new(EmailNotifier::new(smtp)?)),
+            None => None,
+        };
+        let sms: Option<Arc<dyn Notifier>> = config
+            .sms_gateway
+            .as_ref()
+            .map(|x| Arc::new(SmsNotifier::new(x)) as Arc<dyn Notifier>);
+
+        Ok(Notifiers {
+            telegram: Arc::new(TelegramNotifier::new(bot)),
+            email,
+            sms,
+        })
+    }
+
+    fn get(&self, channel: ContactChannel) -> Option<&Arc<dyn Notifier>> {
+        match channel {
+            ContactChannel::Telegram => Some(&self.telegram),
+            ContactChannel::Email => self.email.as_ref(),
+            ContactChannel::Sms => self.sms.as_ref(),
+        }
+    }
+
+    pub fn supports(&self, channel: ContactChannel) -> bool {
+        self.get(channel).is_some()
+    }
+
+    pub async fn notify(
+        &self,
+        link: &secondary_owners::Model,
+        notification: &Notification,
+    ) -> Result<(), NotifyError> {
+        let notifier = match self.get(link.channel) {
+            Some(notifier) => notifier,
+            None => {
+                let message = format!("{:?} contacts are not configured", link.channel);
+                return Err(NotifyError::Permanent(message));
+            }
+        };
+        let address = match get_address(link) {
+            Some(address) => address,
+            None => return Err(NotifyError::Permanent(format!("No address for {:?}", link))),
+        };
+        notifier.notify(&address, notification).await
+    }
+}
+
+pub fn get_address(link: &secondary_owners::Model) -> Option<String> {
+    match link.channel {
+        ContactChannel::Telegram => link.secondary_owner_chat_id.map(|x| x.to_string()),
+        ContactChannel::Email | ContactChannel::Sms => link.address.clone(),
+    }
+}
+
+// Our templates only use simple inline tags and the entities Tera escapes
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for char in html.chars() {
+        match char {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(char),
+            _ => {}
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#x2F;", "/")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
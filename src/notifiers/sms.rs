@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Serialize;
+use url::Url;
+
+use crate::config::SmsGatewayConfig;
+
+use super::{html_to_text, Notification, Notifier, NotifyError};
+
+#[derive(Serialize)]
+struct SmsRequest<'a> {
+    to: &'a str,
+    text: &'a str,
+}
+
+pub struct SmsNotifier {
+    client: reqwest::Client,
+    url: Url,
+    token: Option<String>,
+}
+
+impl SmsNotifier {
+    pub fn new(config: &SmsGatewayConfig) -> Self {
+        SmsNotifier {
+            client: reqwest::Client::new(),
+            url: config.url.clone(),
+            token: config.token.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SmsNotifier {
+    async fn notify(&self, address: &str, notification: &Notification) -> Result<(), NotifyError> {
+        let text = html_to_text(&notification.html);
+        let mut request = self.client.post(self.url.clone()).json(&SmsRequest {
+            to: address,
+            text: &text,
+        });
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let result = request.send().await.and_then(|x| x.error_for_status());
+
+        match result {
+            Ok(_) => Ok(()),
+            // The gateway rejected the request itself, e.g. a bad number
+            Err(err)
+                if err
+                    .status()
+                    .is_some_and(|x| x.is_client_error() && x != StatusCode::TOO_MANY_REQUESTS) =>
+            {
+                Err(NotifyError::Permanent(err.to_string()))
+            }
+            Err(err) => Err(NotifyError::Transient(err.to_string())),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use std::error::Error;
+use teloxide::prelude::*;
+use teloxide::types::{InputFile, ParseMode};
+use teloxide::{ApiError, RequestError};
+
+use crate::modules::emergency_info::send_attachments;
+
+use super::{Notification, Notifier, NotifyError};
+
+pub struct TelegramNotifier {
+    bot: Bot,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot: Bot) -> Self {
+        TelegramNotifier { bot }
+    }
+
+    async fn send(
+        &self,
+        chat_id: ChatId,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let request = self
+            .bot
+            .send_message(chat_id, &notification.html)
+            .parse_mode(ParseMode::Html);
+        match &notification.keyboard {
+            Some(keyboard) => request.reply_markup(keyboard.clone()).await?,
+            None => request.await?,
+        };
+
+        // The alert is out once the text is, a retry would send it again.
+        // Attachments and photos are extras, one failing doesn't hold back the rest.
+        for attachment in &notification.attachments {
+            let result =
+                send_attachments(&self.bot, chat_id, std::slice::from_ref(attachment)).await;
+            if let Err(err) = result {
+                // No captions in the logs, they're encrypted for a reason
+                log::error!(
+                    "Can't send {:?} attachment {} to {}: {}",
+                    attachment.kind,
+                    attachment.id,
+                    chat_id,
+                    err
+                );
+            }
+        }
+        for (file_id, caption) in &notification.photos {
+            let result = self
+                .bot
+                .send_photo(chat_id, InputFile::file_id(file_id))
+                .caption(caption)
+                .await;
+            if let Err(err) = result {
+                log::error!("Can't send photo {} to {}: {}", file_id, chat_id, err);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn notify(&self, address: &str, notification: &Notification) -> Result<(), NotifyError> {
+        let chat_id = match address.parse() {
+            Ok(chat_id) => ChatId(chat_id),
+            Err(_) => return Err(NotifyError::Permanent(format!("Bad chat id: {}", address))),
+        };
+
+        self.send(chat_id, notification).await.map_err(|err| {
+            match err.downcast_ref::<RequestError>() {
+                Some(err) => get_notify_error(err),
+                None => NotifyError::Transient(err.to_string()),
+            }
+        })
+    }
+}
+
+pub fn get_notify_error(err: &RequestError) -> NotifyError {
+    match err {
+        // Unknown API errors include Telegram's own outages
+        RequestError::Api(ApiError::Unknown(_)) => NotifyError::Transient(err.to_string()),
+        RequestError::Api(
+            ApiError::BotBlocked
+            | ApiError::BotKicked
+            | ApiError::BotKickedFromSupergroup
+            | ApiError::UserDeactivated
+            | ApiError::ChatNotFound
+            | ApiError::CantInitiateConversation
+            | ApiError::GroupDeactivated,
+        ) => NotifyError::Unreachable(err.to_string()),
+        RequestError::Api(_) | RequestError::MigrateToChatId(_) => {
+            NotifyError::Permanent(err.to_string())
+        }
+        _ => NotifyError::Transient(err.to_string()),
+    }
+}
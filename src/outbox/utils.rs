@@ -0,0 +1,287 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::prelude::*;
+use sea_orm::{ActiveValue, ConnectionTrait, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, MessageId, ParseMode};
+
+use crate::entity::{
+    outbox_messages::{self, DeliveryStatus},
+    secondary_owners,
+};
+use crate::notifiers::{Notification, NotifyError};
+
+pub const MAX_OUTBOX_ATTEMPTS: i32 = 8;
+pub const OUTBOX_BACKOFF_SECONDS: i64 = 5;
+pub const OUTBOX_BATCH_SIZE: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutboxPayload {
+    Message {
+        text: String,
+        parse_mode: Option<ParseMode>,
+        keyboard: Option<InlineKeyboardMarkup>,
+    },
+    DeleteMessage {
+        message_id: i32,
+    },
+    // Goes out through the contact's channel, so only what every channel can carry
+    Notice {
+        subject: String,
+        html: String,
+        keyboard: Option<InlineKeyboardMarkup>,
+    },
+}
+
+// Doubles with every attempt: 5, 10, 20, 40 seconds and so on
+pub fn get_backoff(attempts: i32) -> Duration {
+    Duration::seconds(OUTBOX_BACKOFF_SECONDS << (attempts - 1).clamp(0, 10))
+}
+
+async fn enqueue<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: Option<i64>,
+    link_id: Option<i32>,
+    payload: OutboxPayload,
+    check_in_prompt: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let now = Utc::now().naive_utc();
+    outbox_messages::Entity::insert(outbox_messages::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id),
+        link_id: ActiveValue::Set(link_id),
+        payload: ActiveValue::Set(serde_json::to_value(payload)?),
+        status: ActiveValue::Set(DeliveryStatus::Pending),
+        next_attempt_at: ActiveValue::Set(now),
+        created_at: ActiveValue::Set(now),
+        check_in_prompt: ActiveValue::Set(check_in_prompt),
+        ..Default::default()
+    })
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(())
+}
+
+// Pass the transaction that makes the state change the message is about,
+// so either both happen or neither does
+pub async fn enqueue_message<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    text: String,
+    parse_mode: Option<ParseMode>,
+    keyboard: Option<InlineKeyboardMarkup>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let payload = OutboxPayload::Message {
+        text,
+        parse_mode,
+        keyboard,
+    };
+    enqueue(connection, Some(chat_id.0), None, payload, false).await
+}
+
+// The escalation clock starts when the dispatcher delivers it, not when it's queued
+pub async fn enqueue_check_in_prompt<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    text: String,
+    keyboard: InlineKeyboardMarkup,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let payload = OutboxPayload::Message {
+        text,
+        parse_mode: None,
+        keyboard: Some(keyboard),
+    };
+    enqueue(connection, Some(chat_id.0), None, payload, true).await
+}
+
+// Queued but not delivered yet, the owner doesn't need another one
+pub async fn has_pending_check_in_prompt<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let count = outbox_messages::Entity::find()
+        .filter(outbox_messages::Column::ChatId.eq(chat_id.0))
+        .filter(outbox_messages::Column::CheckInPrompt.eq(true))
+        .filter(outbox_messages::Column::Status.eq(DeliveryStatus::Pending))
+        .count(connection)
+        .await?;
+
+    Ok(count > 0)
+}
+
+// The owner checked in on their own, a late prompt would look unanswered
+pub async fn cancel_check_in_prompts<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    outbox_messages::Entity::delete_many()
+        .filter(outbox_messages::Column::ChatId.eq(chat_id.0))
+        .filter(outbox_messages::Column::CheckInPrompt.eq(true))
+        .filter(outbox_messages::Column::Status.eq(DeliveryStatus::Pending))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn enqueue_delete_message<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    message_id: MessageId,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let payload = OutboxPayload::DeleteMessage {
+        message_id: message_id.0,
+    };
+    enqueue(connection, Some(chat_id.0), None, payload, false).await
+}
+
+// Attachments and photos are left out, alerts that carry them have their own deliveries
+pub async fn enqueue_notice<C: ConnectionTrait>(
+    connection: &C,
+    link: &secondary_owners::Model,
+    notification: &Notification,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let payload = OutboxPayload::Notice {
+        subject: notification.subject.clone(),
+        html: notification.html.clone(),
+        keyboard: notification.keyboard.clone(),
+    };
+    enqueue(
+        connection,
+        link.secondary_owner_chat_id,
+        Some(link.id),
+        payload,
+        false,
+    )
+    .await
+}
+
+pub fn get_payload(
+    message: &outbox_messages::Model,
+) -> Result<OutboxPayload, Box<dyn Error + Send + Sync>> {
+    Ok(serde_json::from_value(message.payload.clone())?)
+}
+
+// Oldest first. A message waits while anything queued before it for the same
+// chat or contact is still pending, so recipients see them in order.
+pub fn select_due_messages(now: NaiveDateTime) -> Select<outbox_messages::Entity> {
+    outbox_messages::Entity::find()
+        .filter(outbox_messages::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(outbox_messages::Column::NextAttemptAt.lte(now))
+        .filter(Expr::cust(
+            "NOT EXISTS (SELECT 1 FROM outbox_messages AS earlier \
+             WHERE earlier.status = 'pending' AND earlier.id < outbox_messages.id \
+             AND (earlier.chat_id = outbox_messages.chat_id \
+             OR earlier.link_id = outbox_messages.link_id))",
+        ))
+        .order_by_asc(outbox_messages::Column::Id)
+        .limit(OUTBOX_BATCH_SIZE)
+}
+
+// Same as with alert deliveries: the attempt is counted before sending, so a
+// crash can't make the dispatcher resend a message in a tight loop
+pub async fn claim_message(
+    connection: &DatabaseConnection,
+    message: &outbox_messages::Model,
+    now: NaiveDateTime,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let attempts = message.attempts + 1;
+    let result = outbox_messages::Entity::update_many()
+        .set(outbox_messages::ActiveModel {
+            attempts: ActiveValue::Set(attempts),
+            next_attempt_at: ActiveValue::Set(now + get_backoff(attempts)),
+            ..Default::default()
+        })
+        .filter(outbox_messages::Column::Id.eq(message.id))
+        .filter(outbox_messages::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(outbox_messages::Column::Attempts.eq(message.attempts))
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+// Returns `false` if the message was cancelled while it was being sent
+pub async fn mark_message_sent<C: ConnectionTrait>(
+    connection: &C,
+    message_id: i32,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let result = outbox_messages::Entity::update_many()
+        .set(outbox_messages::ActiveModel {
+            status: ActiveValue::Set(DeliveryStatus::Sent),
+            sent_at: ActiveValue::Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        })
+        .filter(outbox_messages::Column::Id.eq(message_id))
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+// Telegram told us to slow down, the attempt doesn't count
+pub async fn postpone_message(
+    connection: &DatabaseConnection,
+    message: &outbox_messages::Model,
+    until: NaiveDateTime,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    outbox_messages::Entity::update_many()
+        .set(outbox_messages::ActiveModel {
+            attempts: ActiveValue::Set(message.attempts),
+            next_attempt_at: ActiveValue::Set(until),
+            ..Default::default()
+        })
+        .filter(outbox_messages::Column::Id.eq(message.id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Nothing queued for a blocked or deleted chat will get through
+pub async fn fail_pending_messages<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    error: &NotifyError,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    outbox_messages::Entity::update_many()
+        .set(outbox_messages::ActiveModel {
+            status: ActiveValue::Set(DeliveryStatus::Failed),
+            last_error: ActiveValue::Set(Some(error.to_string())),
+            ..Default::default()
+        })
+        .filter(outbox_messages::Column::ChatId.eq(chat_id.0))
+        .filter(outbox_messages::Column::Status.eq(DeliveryStatus::Pending))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Expects a claimed message, returns whether it's still worth retrying
+pub async fn record_message_error(
+    connection: &DatabaseConnection,
+    message: &outbox_messages::Model,
+    error: &NotifyError,
+) -> Result<DeliveryStatus, Box<dyn Error + Send + Sync>> {
+    let status = match error {
+        NotifyError::Transient(_) if message.attempts + 1 < MAX_OUTBOX_ATTEMPTS => {
+            DeliveryStatus::Pending
+        }
+        _ => DeliveryStatus::Failed,
+    };
+
+    outbox_messages::Entity::update_many()
+        .set(outbox_messages::ActiveModel {
+            status: ActiveValue::Set(status),
+            last_error: ActiveValue::Set(Some(error.to_string())),
+            ..Default::default()
+        })
+        .filter(outbox_messages::Column::Id.eq(message.id))
+        .exec(connection)
+        .await?;
+
+    Ok(status)
+}
@@ -0,0 +1,240 @@
+use sea_orm::prelude::*;
+use sea_orm::{ActiveValue, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::crypto::{Keyring, Sealed};
+use crate::entity::pets;
+use crate::types::PetField;
+
+pub const PET_FIELDS: [PetField; 6] = [
+    PetField::Name,
+    PetField::Species,
+    PetField::Photo,
+    PetField::MedicalNotes,
+    PetField::Diet,
+    PetField::VetContact,
+];
+
+pub fn get_pet_field_code(field: PetField) -> &'static str {
+    match field {
+        PetField::Name => "name",
+        PetField::Species => "species",
+        PetField::Photo => "photo",
+        PetField::MedicalNotes => "medical_notes",
+        PetField::Diet => "diet",
+        PetField::VetContact => "vet_contact",
+    }
+}
+
+pub fn parse_pet_field(code: &str) -> Option<PetField> {
+    PET_FIELDS
+        .into_iter()
+        .find(|field| get_pet_field_code(*field) == code)
+}
+
+pub fn select_pets(chat_id: ChatId) -> Select<pets::Entity> {
+    pets::Entity::find()
+        .filter(pets::Column::ChatId.eq(chat_id.0))
+        .order_by_asc(pets::Column::Id)
+}
+
+// Scoped to the owner, so a forged callback can't reach someone else's pet
+pub fn select_pet(chat_id: ChatId, pet_id: i32) -> Select<pets::Entity> {
+    select_pets(chat_id).filter(pets::Column::Id.eq(pet_id))
+}
+
+// The fields sealed together in the care envelope
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PetCare {
+    pub medical_notes: Option<String>,
+    pub diet: Option<String>,
+    pub vet_contact: Option<String>,
+}
+
+impl PetCare {
+    fn is_empty(&self) -> bool {
+        self.medical_notes.is_none() && self.diet.is_none() && self.vet_contact.is_none()
+    }
+}
+
+// A pet with its care notes decrypted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Pet {
+    pub id: i32,
+    pub name: String,
+    pub species: Option<String>,
+    pub photo_file_id: Option<String>,
+    #[serde(flatten)]
+    pub care: PetCare,
+}
+
+fn get_sealed_care(row: &pets::Model) -> Option<Sealed> {
+    Some(Sealed {
+        ciphertext: row.care_ciphertext.clone()?,
+        nonce: row.care_nonce.clone()?,
+        data_key: row.care_data_key.clone()?,
+        key_id: row.care_key_id.clone()?,
+    })
+}
+
+fn open_care(
+    keyring: &Keyring,
+    row: &pets::Model,
+) -> Result<PetCare, Box<dyn Error + Send + Sync>> {
+    match get_sealed_care(row) {
+        Some(sealed) => Ok(serde_json::from_str(&keyring.open(&sealed)?)?),
+        None => Ok(PetCare::default()),
+    }
+}
+
+// Care notes that can't be decrypted are dropped, the rest of the pet still shows
+fn open_pet(keyring: &Keyring, row: pets::Model) -> Pet {
+    let care = match open_care(keyring, &row) {
+        Ok(care) => care,
+        Err(err) => {
+            log::error!("Can't decrypt care notes of pet {}: {}", row.id, err);
+            PetCare::default()
+        }
+    };
+    Pet {
+        id: row.id,
+        name: row.name,
+        species: row.species,
+        photo_file_id: row.photo_file_id,
+        care,
+    }
+}
+
+pub async fn get_pets(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+) -> Result<Vec<Pet>, Box<dyn Error + Send + Sync>> {
+    let rows = select_pets(chat_id).all(connection).await?;
+    Ok(rows.into_iter().map(|row| open_pet(keyring, row)).collect())
+}
+
+pub async fn get_pet(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+    pet_id: i32,
+) -> Result<Option<Pet>, Box<dyn Error + Send + Sync>> {
+    let row = select_pet(chat_id, pet_id).one(connection).await?;
+    Ok(row.map(|row| open_pet(keyring, row)))
+}
+
+pub async fn add_pet(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    name: String,
+) -> Result<pets::Model, Box<dyn Error + Send + Sync>> {
+    let pet = pets::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        name: ActiveValue::Set(name),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    Ok(pet)
+}
+
+// `None` clears the field, the name can't be cleared
+pub async fn set_pet_field(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+    chat_id: ChatId,
+    pet_id: i32,
+    field: PetField,
+    value: Option<String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let row = match select_pet(chat_id, pet_id).one(connection).await? {
+        Some(row) => row,
+        None => return Ok(()),
+    };
+
+    let mut pet = pets::ActiveModel {
+        id: ActiveValue::Unchanged(row.id),
+        ..Default::default()
+    };
+    match field {
+        PetField::Name => match value {
+            Some(value) => pet.name = ActiveValue::Set(value),
+            None => return Err("Pet name can't be empty".into()),
+        },
+        PetField::Species => pet.species = ActiveValue::Set(value),
+        PetField::Photo => pet.photo_file_id = ActiveValue::Set(value),
+        PetField::MedicalNotes | PetField::Diet | PetField::VetContact => {
+            // Fails rather than overwrite notes it can't read
+            let mut care = open_care(keyring, &row)?;
+            match field {
+                PetField::MedicalNotes => care.medical_notes = value,
+                PetField::Diet => care.diet = value,
+                _ => care.vet_contact = value,
+            }
+            let sealed = match care.is_empty() {
+                true => None,
+                false => Some(keyring.seal(&serde_json::to_string(&care)?)?),
+            };
+            pet.care_ciphertext = ActiveValue::Set(sealed.as_ref().map(|x| x.ciphertext.clone()));
+            pet.care_nonce = ActiveValue::Set(sealed.as_ref().map(|x| x.nonce.clone()));
+            pet.care_data_key = ActiveValue::Set(sealed.as_ref().map(|x| x.data_key.clone()));
+            pet.care_key_id = ActiveValue::Set(sealed.map(|x| x.key_id));
+        }
+    }
+    pet.update(connection).await?;
+
+    Ok(())
+}
+
+pub async fn rotate_care_keys(
+    connection: &DatabaseConnection,
+    keyring: &Keyring,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let rows = pets::Entity::find()
+        .filter(pets::Column::CareKeyId.is_not_null())
+        .filter(pets::Column::CareKeyId.ne(keyring.active_key_id()))
+        .all(connection)
+        .await?;
+
+    let mut rotated = 0;
+    for row in rows {
+        let sealed = match get_sealed_care(&row).map(|sealed| keyring.rewrap(&sealed)) {
+            Some(Ok(sealed)) => sealed,
+            Some(Err(err)) => {
+                log::error!("Can't rotate care notes of pet {}: {}", row.id, err);
+                continue;
+            }
+            None => continue,
+        };
+
+        pets::ActiveModel {
+            id: ActiveValue::Unchanged(row.id),
+            care_data_key: ActiveValue::Set(Some(sealed.data_key)),
+            care_key_id: ActiveValue::Set(Some(sealed.key_id)),
+            ..Default::default()
+        }
+        .update(connection)
+        .await?;
+        rotated += 1;
+    }
+
+    Ok(rotated)
+}
+
+pub async fn remove_pet(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    pet_id: i32,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pets::Entity::delete_many()
+        .filter(pets::Column::ChatId.eq(chat_id.0))
+        .filter(pets::Column::Id.eq(pet_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
@@ -1,7 +1,16 @@
-use sea_orm::{prelude::*, JoinType, QuerySelect};
+use chrono::Utc;
+use chrono_tz::Tz;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{prelude::*, ActiveValue, JoinType, QueryOrder, QuerySelect};
+use std::error::Error;
 use teloxide::prelude::*;
 
-use crate::entity::{profiles, secondary_owners, statuses};
+use crate::entity::{
+    profiles::{self, Language},
+    secondary_owners::{self, ContactChannel, ContactTier},
+    statuses,
+};
+use crate::i18n::Text;
 
 pub fn select_active_profiles() -> Select<profiles::Entity> {
     profiles::Entity::find()
@@ -11,6 +20,8 @@ pub fn select_active_profiles() -> Select<profiles::Entity> {
         )
         // Is enabled
         .filter(statuses::Column::Enabled.eq(true))
+        // Can still get the check-in prompts
+        .filter(profiles::Column::UnreachableAt.is_null())
         // Is there at least 1 emergency contact
         .join_rev(
             JoinType::InnerJoin,
@@ -27,7 +38,223 @@ pub fn select_profile(chat_id: ChatId) -> Select<profiles::Entity> {
     profiles::Entity::find().filter(profiles::Column::ChatId.eq(chat_id.0))
 }
 
+// Returns `false` if the chat was already known to be unreachable
+pub async fn mark_unreachable<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let result = profiles::Entity::update_many()
+        .col_expr(
+            profiles::Column::UnreachableAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(profiles::Column::ChatId.eq(chat_id.0))
+        .filter(profiles::Column::UnreachableAt.is_null())
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+// The fallback is shown to the reader, so it's translated to their language
+pub async fn get_display_name<C: ConnectionTrait>(
+    connection: &C,
+    chat_id: ChatId,
+    language: Language,
+) -> String {
+    select_profile(chat_id)
+        .one(connection)
+        .await
+        .ok()
+        .flatten()
+        .map_or_else(
+            || Text::PetOwner.get(language).to_owned(),
+            |x| format!("@{}", x.username),
+        )
+}
+
+// Contacts without Telegram are shown by their address
+pub async fn get_contact_name<C: ConnectionTrait>(
+    connection: &C,
+    link: &secondary_owners::Model,
+    language: Language,
+) -> String {
+    match link.secondary_owner_chat_id {
+        Some(chat_id) => get_display_name(connection, ChatId(chat_id), language).await,
+        None => link.address.clone().unwrap_or_default(),
+    }
+}
+
+// Contacts without Telegram have no profile, they get the owner's language
+pub async fn get_contact_language<C: ConnectionTrait>(
+    connection: &C,
+    link: &secondary_owners::Model,
+) -> Language {
+    let chat_id = link
+        .secondary_owner_chat_id
+        .unwrap_or(link.primary_owner_chat_id);
+    get_language(connection, ChatId(chat_id)).await
+}
+
+// Phone numbers must be in the international format, e.g. +44 20 7946 0958
+pub fn parse_contact_address(text: &str) -> Option<(ContactChannel, String)> {
+    let text = text.trim();
+    if text.contains('@') {
+        let address = text.parse::<lettre::Address>().ok()?;
+        return Some((ContactChannel::Email, address.to_string()));
+    }
+
+    let digits = text
+        .strip_prefix('+')?
+        .chars()
+        .filter(|x| !matches!(x, ' ' | '-' | '(' | ')'))
+        .collect::<String>();
+    if (8..=15).contains(&digits.len()) && digits.chars().all(|x| x.is_ascii_digit()) {
+        Some((ContactChannel::Sms, format!("+{}", digits)))
+    } else {
+        None
+    }
+}
+
+// Returns `false` if the owner already has this address
+pub async fn add_external_contact(
+    connection: &DatabaseConnection,
+    primary_owner_chat_id: ChatId,
+    channel: ContactChannel,
+    address: &str,
+) -> Result<bool, Box<dyn Error + Sync + Send>> {
+    let result = secondary_owners::Entity::insert(secondary_owners::ActiveModel {
+        primary_owner_chat_id: ActiveValue::Set(primary_owner_chat_id.0),
+        secondary_owner_chat_id: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        channel: ActiveValue::Set(channel),
+        address: ActiveValue::Set(Some(address.to_string())),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([
+            secondary_owners::Column::PrimaryOwnerChatId,
+            secondary_owners::Column::Address,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(connection)
+    .await?;
+
+    Ok(result == 1)
+}
+
+// Ordered the way they get alerted
 pub fn select_emergency_contacts(chat_id: ChatId) -> Select<secondary_owners::Entity> {
     secondary_owners::Entity::find()
         .filter(secondary_owners::Column::PrimaryOwnerChatId.eq(chat_id.0))
+        .order_by_asc(secondary_owners::Column::Tier)
+        .order_by_asc(secondary_owners::Column::Id)
+}
+
+pub async fn is_emergency_contact(
+    connection: &DatabaseConnection,
+    primary_owner_chat_id: ChatId,
+    secondary_owner_chat_id: ChatId,
+) -> Result<bool, Box<dyn Error + Sync + Send>> {
+    let link = select_emergency_contacts(primary_owner_chat_id)
+        .filter(secondary_owners::Column::SecondaryOwnerChatId.eq(secondary_owner_chat_id.0))
+        .one(connection)
+        .await?;
+
+    Ok(link.is_some())
+}
+
+// Owners the contact backs up
+pub fn select_backed_up_owners(chat_id: ChatId) -> Select<secondary_owners::Entity> {
+    secondary_owners::Entity::find()
+        .filter(secondary_owners::Column::SecondaryOwnerChatId.eq(chat_id.0))
+        .order_by_asc(secondary_owners::Column::Id)
+}
+
+pub async fn set_contact_tier(
+    connection: &DatabaseConnection,
+    primary_owner_chat_id: ChatId,
+    link_id: i32,
+    tier: ContactTier,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    secondary_owners::Entity::update_many()
+        .col_expr(secondary_owners::Column::Tier, Expr::value(tier))
+        .filter(secondary_owners::Column::PrimaryOwnerChatId.eq(primary_owner_chat_id.0))
+        .filter(secondary_owners::Column::Id.eq(link_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Returns `false` if the link is already gone
+pub async fn remove_emergency_contact<C: ConnectionTrait>(
+    connection: &C,
+    link: &secondary_owners::Model,
+) -> Result<bool, Box<dyn Error + Sync + Send>> {
+    let result = secondary_owners::Entity::delete_by_id(link.id)
+        .exec(connection)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+pub fn parse_time_zone(name: &str) -> Option<Tz> {
+    name.trim().parse::<Tz>().ok()
+}
+
+pub fn get_time_zone(profile: &profiles::Model) -> Tz {
+    parse_time_zone(&profile.time_zone).unwrap_or(Tz::UTC)
+}
+
+pub async fn set_time_zone(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    time_zone: Tz,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    profiles::Entity::update_many()
+        .set(profiles::ActiveModel {
+            time_zone: ActiveValue::Set(time_zone.name().to_string()),
+            ..Default::default()
+        })
+        .filter(profiles::Column::ChatId.eq(chat_id.0))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Accepts IETF tags from Telegram like "en" or "en-US"
+pub fn parse_language(code: &str) -> Option<Language> {
+    let code = code.trim().to_lowercase();
+    let code = code.split(['-', '_']).next().unwrap_or_default();
+    Language::try_from_value(&code.to_string()).ok()
+}
+
+pub async fn get_language<C: ConnectionTrait>(connection: &C, chat_id: ChatId) -> Language {
+    select_profile(chat_id)
+        .one(connection)
+        .await
+        .ok()
+        .flatten()
+        .map_or(Language::default(), |x| x.language)
+}
+
+pub async fn set_language(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    language: Language,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    profiles::Entity::update_many()
+        .set(profiles::ActiveModel {
+            language: ActiveValue::Set(language),
+            ..Default::default()
+        })
+        .filter(profiles::Column::ChatId.eq(chat_id.0))
+        .exec(connection)
+        .await?;
+
+    Ok(())
 }
@@ -0,0 +1,334 @@
+use chrono::{NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::{OnConflict, SimpleExpr};
+use sea_orm::{ActiveValue, ColumnTrait};
+use std::error::Error;
+use teloxide::prelude::*;
+
+use crate::entity::settings;
+
+pub const DEFAULT_CHECK_IN_INTERVAL_HOURS: i32 = 24;
+pub const DEFAULT_ALERT_GRACE_HOURS: i32 = 24;
+
+pub const CHECK_IN_INTERVAL_OPTIONS: [i32; 4] = [8, 12, 24, 72];
+pub const ALERT_GRACE_OPTIONS: [i32; 4] = [12, 24, 48, 72];
+
+// Local hours, `(start, end)`, an empty range means "no restriction"
+pub const CHECK_IN_WINDOW_OPTIONS: [(i32, i32); 4] = [(9, 11), (12, 14), (18, 20), (0, 0)];
+pub const QUIET_HOURS_OPTIONS: [(i32, i32); 4] = [(22, 8), (23, 7), (0, 9), (0, 0)];
+
+pub async fn get_settings(connection: &DatabaseConnection, chat_id: ChatId) -> settings::Model {
+    settings::Entity::find()
+        .filter(settings::Column::ChatId.eq(chat_id.0))
+        .one(connection)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(settings::Model {
+            id: 0,
+            chat_id: chat_id.0,
+            check_in_interval_hours: DEFAULT_CHECK_IN_INTERVAL_HOURS,
+            alert_grace_hours: DEFAULT_ALERT_GRACE_HOURS,
+            check_in_window_start: None,
+            check_in_window_end: None,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+        })
+}
+
+pub async fn set_check_in_interval(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    hours: i32,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    settings::Entity::insert(settings::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        check_in_interval_hours: ActiveValue::Set(hours),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(settings::Column::ChatId)
+            .update_column(settings::Column::CheckInIntervalHours)
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_alert_grace(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    hours: i32,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    settings::Entity::insert(settings::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        alert_grace_hours: ActiveValue::Set(hours),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(settings::Column::ChatId)
+            .update_column(settings::Column::AlertGraceHours)
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+fn to_range(start: i32, end: i32) -> (Option<i32>, Option<i32>) {
+    if start == end {
+        (None, None)
+    } else {
+        (Some(start), Some(end))
+    }
+}
+
+pub async fn set_check_in_window(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    start: i32,
+    end: i32,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (start, end) = to_range(start, end);
+    settings::Entity::insert(settings::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        check_in_window_start: ActiveValue::Set(start),
+        check_in_window_end: ActiveValue::Set(end),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(settings::Column::ChatId)
+            .update_columns([
+                settings::Column::CheckInWindowStart,
+                settings::Column::CheckInWindowEnd,
+            ])
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_quiet_hours(
+    connection: &DatabaseConnection,
+    chat_id: ChatId,
+    start: i32,
+    end: i32,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (start, end) = to_range(start, end);
+    settings::Entity::insert(settings::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id.0),
+        quiet_hours_start: ActiveValue::Set(start),
+        quiet_hours_end: ActiveValue::Set(end),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(settings::Column::ChatId)
+            .update_columns([
+                settings::Column::QuietHoursStart,
+                settings::Column::QuietHoursEnd,
+            ])
+            .to_owned(),
+    )
+    .exec(connection)
+    .await?;
+
+    Ok(())
+}
+
+// Hours are local, a range with `start > end` wraps around midnight
+fn is_hour_in_range(hour: i32, start: Option<i32>, end: Option<i32>) -> Option<bool> {
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => Some(start <= hour && hour < end),
+        (Some(start), Some(end)) if start > end => Some(start <= hour || hour < end),
+        _ => None,
+    }
+}
+
+pub fn is_quiet_time(settings: &settings::Model, time_zone: Tz, now: NaiveDateTime) -> bool {
+    let hour = now.and_utc().with_timezone(&time_zone).hour() as i32;
+    is_hour_in_range(hour, settings.quiet_hours_start, settings.quiet_hours_end).unwrap_or(false)
+}
+
+pub fn is_check_in_window_open(
+    settings: &settings::Model,
+    time_zone: Tz,
+    now: NaiveDateTime,
+) -> bool {
+    let hour = now.and_utc().with_timezone(&time_zone).hour() as i32;
+    let in_window = is_hour_in_range(
+        hour,
+        settings.check_in_window_start,
+        settings.check_in_window_end,
+    )
+    .unwrap_or(true);
+
+    in_window && !is_quiet_time(settings, time_zone, now)
+}
+
+// Expects `monitoring_settings` and `alive_events` to be joined to the query
+pub fn is_check_in_overdue() -> SimpleExpr {
+    Expr::cust_with_values(
+        "alive_events.timestamp < $1 - make_interval(hours => COALESCE(monitoring_settings.check_in_interval_hours, $2))",
+        [
+            Value::from(chrono::Utc::now().naive_utc()),
+            Value::from(DEFAULT_CHECK_IN_INTERVAL_HOURS),
+        ],
+    )
+}
+
+// Expects `alive_events` and `prompt_events` to be joined to the query
+pub fn is_prompt_pending() -> SimpleExpr {
+    Expr::cust(
+        "(alive_events.timestamp IS NULL OR prompt_events.timestamp > alive_events.timestamp)",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn get_settings(
+        window: Option<(i32, i32)>,
+        quiet_hours: Option<(i32, i32)>,
+    ) -> settings::Model {
+        settings::Model {
+            id: 1,
+            chat_id: 1,
+            check_in_interval_hours: 24,
+            alert_grace_hours: 12,
+            check_in_window_start: window.map(|x| x.0),
+            check_in_window_end: window.map(|x| x.1),
+            quiet_hours_start: quiet_hours.map(|x| x.0),
+            quiet_hours_end: quiet_hours.map(|x| x.1),
+        }
+    }
+
+    fn at_utc(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 7, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn same_day_range_includes_start_and_excludes_end() {
+        let cases = [
+            (8, false),
+            (9, true),
+            (15, true),
+            (20, true),
+            (21, false),
+            (0, false),
+        ];
+        for (hour, expected) in cases {
+            assert_eq!(
+                is_hour_in_range(hour, Some(9), Some(21)),
+                Some(expected),
+                "{}",
+                hour
+            );
+        }
+    }
+
+    #[test]
+    fn range_wraps_past_midnight() {
+        let cases = [
+            (21, false),
+            (22, true),
+            (23, true),
+            (0, true),
+            (6, true),
+            (7, false),
+            (12, false),
+        ];
+        for (hour, expected) in cases {
+            assert_eq!(
+                is_hour_in_range(hour, Some(22), Some(7)),
+                Some(expected),
+                "{}",
+                hour
+            );
+        }
+    }
+
+    #[test]
+    fn empty_or_missing_range_is_unset() {
+        assert_eq!(is_hour_in_range(10, None, None), None);
+        assert_eq!(is_hour_in_range(10, Some(9), None), None);
+        assert_eq!(is_hour_in_range(10, Some(9), Some(9)), None);
+    }
+
+    #[test]
+    fn quiet_time_uses_the_owner_time_zone() {
+        // Moscow is UTC+3 all year
+        let settings = get_settings(None, Some((22, 7)));
+        let time_zone = Tz::Europe__Moscow;
+        assert!(!is_quiet_time(&settings, time_zone, at_utc(18, 59)));
+        assert!(is_quiet_time(&settings, time_zone, at_utc(19, 0)));
+        assert!(is_quiet_time(&settings, time_zone, at_utc(3, 59)));
+        assert!(!is_quiet_time(&settings, time_zone, at_utc(4, 0)));
+        // The same hours in UTC
+        assert!(!is_quiet_time(&settings, Tz::UTC, at_utc(19, 0)));
+        assert!(is_quiet_time(&settings, Tz::UTC, at_utc(22, 0)));
+        assert!(!is_quiet_time(
+            &get_settings(None, None),
+            time_zone,
+            at_utc(0, 0)
+        ));
+    }
+
+    #[test]
+    fn check_in_window_uses_the_owner_time_zone() {
+        // New York is UTC-4 in July
+        let time_zone = Tz::America__New_York;
+        let settings = get_settings(Some((9, 21)), None);
+        assert!(!is_check_in_window_open(
+            &settings,
+            time_zone,
+            at_utc(12, 59)
+        ));
+        assert!(is_check_in_window_open(&settings, time_zone, at_utc(13, 0)));
+        assert!(is_check_in_window_open(&settings, time_zone, at_utc(0, 59)));
+        assert!(!is_check_in_window_open(&settings, time_zone, at_utc(1, 0)));
+
+        // Without a window prompts can go out any time
+        let settings = get_settings(None, None);
+        assert!(is_check_in_window_open(&settings, time_zone, at_utc(7, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_close_the_check_in_window() {
+        let time_zone = Tz::America__New_York;
+        let settings = get_settings(Some((9, 21)), Some((12, 14)));
+        assert!(is_check_in_window_open(
+            &settings,
+            time_zone,
+            at_utc(15, 59)
+        ));
+        assert!(!is_check_in_window_open(
+            &settings,
+            time_zone,
+            at_utc(16, 0)
+        ));
+        assert!(!is_check_in_window_open(
+            &settings,
+            time_zone,
+            at_utc(17, 59)
+        ));
+        assert!(is_check_in_window_open(&settings, time_zone, at_utc(18, 0)));
+
+        // A window wrapping past midnight with quiet hours in it
+        let settings = get_settings(Some((20, 8)), Some((23, 6)));
+        assert!(is_check_in_window_open(&settings, Tz::UTC, at_utc(22, 0)));
+        assert!(!is_check_in_window_open(&settings, Tz::UTC, at_utc(2, 0)));
+        assert!(is_check_in_window_open(&settings, Tz::UTC, at_utc(7, 0)));
+        assert!(!is_check_in_window_open(&settings, Tz::UTC, at_utc(12, 0)));
+    }
+}
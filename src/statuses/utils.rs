@@ -15,8 +15,8 @@ pub async fn is_enabled(connection: &DatabaseConnection, chat_id: ChatId) -> boo
         .map_or(false, |x| x.enabled)
 }
 
-pub async fn set_monitoring(
-    connection: &DatabaseConnection,
+pub async fn set_monitoring<C: ConnectionTrait>(
+    connection: &C,
     chat_id: ChatId,
     status: bool,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
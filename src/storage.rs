@@ -0,0 +1,136 @@
+use sea_orm::prelude::*;
+use sea_orm::{sea_query::OnConflict, ActiveValue};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use teloxide::dispatching::dialogue::Storage;
+use teloxide::types::ChatId;
+
+use crate::entity::dialogues;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+#[derive(Debug)]
+pub enum DatabaseStorageError {
+    DialogueNotFound,
+    Database(DbErr),
+    Serialization(serde_json::Error),
+}
+
+impl Display for DatabaseStorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseStorageError::DialogueNotFound => write!(f, "row not found"),
+            DatabaseStorageError::Database(error) => write!(f, "database error: {}", error),
+            DatabaseStorageError::Serialization(error) => {
+                write!(f, "serialization error: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatabaseStorageError {}
+
+impl From<DbErr> for DatabaseStorageError {
+    fn from(error: DbErr) -> Self {
+        DatabaseStorageError::Database(error)
+    }
+}
+
+impl From<serde_json::Error> for DatabaseStorageError {
+    fn from(error: serde_json::Error) -> Self {
+        DatabaseStorageError::Serialization(error)
+    }
+}
+
+// Keeps dialogue states in Postgres, so they survive restarts and are shared between replicas
+pub struct DatabaseStorage {
+    connection: DatabaseConnection,
+}
+
+impl DatabaseStorage {
+    pub fn new(connection: DatabaseConnection) -> Arc<Self> {
+        Arc::new(Self { connection })
+    }
+}
+
+impl<D> Storage<D> for DatabaseStorage
+where
+    D: Serialize + DeserializeOwned + Send + 'static,
+{
+    type Error = DatabaseStorageError;
+
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let result = dialogues::Entity::delete_many()
+                .filter(dialogues::Column::ChatId.eq(chat_id.0))
+                .exec(&self.connection)
+                .await?;
+
+            if result.rows_affected == 0 {
+                return Err(DatabaseStorageError::DialogueNotFound);
+            }
+            Ok(())
+        })
+    }
+
+    fn update_dialogue(
+        self: Arc<Self>,
+        chat_id: ChatId,
+        dialogue: D,
+    ) -> BoxFuture<Result<(), Self::Error>>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let state = serde_json::to_string(&dialogue)?;
+            dialogues::Entity::insert(dialogues::ActiveModel {
+                chat_id: ActiveValue::Set(chat_id.0),
+                state: ActiveValue::Set(state),
+                ..Default::default()
+            })
+            .on_conflict(
+                OnConflict::column(dialogues::Column::ChatId)
+                    .update_column(dialogues::Column::State)
+                    .to_owned(),
+            )
+            .exec(&self.connection)
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<Result<Option<D>, Self::Error>> {
+        Box::pin(async move {
+            let dialogue = dialogues::Entity::find()
+                .filter(dialogues::Column::ChatId.eq(chat_id.0))
+                .one(&self.connection)
+                .await?;
+
+            let dialogue = match dialogue {
+                Some(dialogue) => dialogue,
+                None => return Ok(None),
+            };
+            match serde_json::from_str(&dialogue.state) {
+                Ok(state) => Ok(Some(state)),
+                // Left over from an older version of the states. teloxide drops
+                // updates on storage errors, so the chat would be stuck for good.
+                Err(error) => {
+                    log::error!("Resetting unreadable dialogue {:?}: {}", dialogue, error);
+                    dialogues::Entity::delete_many()
+                        .filter(dialogues::Column::Id.eq(dialogue.id))
+                        .filter(dialogues::Column::State.eq(dialogue.state))
+                        .exec(&self.connection)
+                        .await?;
+                    Ok(None)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use teloxide::dispatching::Dispatcher;
+use teloxide::error_handlers::LoggingErrorHandler;
+use teloxide::prelude::*;
+use teloxide::update_listeners::webhooks;
+
+use crate::config::{Transport, WebhookConfig};
+
+// The listener rejects requests without a matching `X-Telegram-Bot-Api-Secret-Token` header
+pub fn webhook_options(config: &WebhookConfig) -> webhooks::Options {
+    webhooks::Options::new(config.address, config.url.clone()).secret_token(config.secret.clone())
+}
+
+pub async fn dispatch<Err, Key>(
+    mut dispatcher: Dispatcher<Bot, Err, Key>,
+    bot: Bot,
+    transport: Transport,
+) where
+    Err: Debug + Send + Sync + 'static,
+    Key: Hash + Eq + Clone + Send + 'static,
+{
+    match transport {
+        Transport::Polling => {
+            log::info!("Started polling...");
+            dispatcher.dispatch().await
+        }
+        Transport::Webhook(config) => {
+            let listener = webhooks::axum(bot, webhook_options(&config))
+                .await
+                .expect("Couldn't set up webhook");
+            log::info!("Started listening on {}...", config.address);
+            dispatcher
+                .dispatch_with_listener(
+                    listener,
+                    LoggingErrorHandler::with_custom_text("An error from the update listener"),
+                )
+                .await
+        }
+    }
+}
@@ -1,11 +1,37 @@
-use teloxide::dispatching::dialogue::{Dialogue, InMemStorage};
+use serde::{Deserialize, Serialize};
+use teloxide::dispatching::dialogue::Dialogue;
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+use crate::entity::emergency_info::EmergencySection;
+use crate::storage::DatabaseStorage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PetField {
+    Name,
+    Species,
+    Photo,
+    MedicalNotes,
+    Diet,
+    VetContact,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BotDialogState {
     #[default]
     Idle,
-    WaitingEmergencyText,
+    // `wizard` moves on to the next section once the answer is saved
+    WaitingEmergencySection {
+        section: EmergencySection,
+        wizard: bool,
+    },
+    WaitingEmergencyAttachment,
     WaitingForInvite,
+    WaitingForExternalContact,
+    WaitingForTimeZone,
+    WaitingForPetName,
+    WaitingForPetField {
+        pet_id: i32,
+        field: PetField,
+    },
 }
 
-pub type BotDialogue = Dialogue<BotDialogState, InMemStorage<BotDialogState>>;
+pub type BotDialogue = Dialogue<BotDialogState, DatabaseStorage>;
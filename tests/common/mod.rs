@@ -0,0 +1,201 @@
+// Not every test binary uses every helper
+#![allow(dead_code)]
+
+use axum::{extract::State, http::Uri, Router};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, Database, DatabaseConnection};
+use std::env;
+use std::net::{SocketAddr, TcpListener};
+use teloxide::prelude::*;
+use tera::Tera;
+use tokio::sync::{mpsc, Mutex, MutexGuard};
+use trusty_tail::config::{Config, Transport};
+use trusty_tail::crypto::Keyring;
+use trusty_tail::entity::{
+    alive_events, profiles, secondary_owners,
+    secondary_owners::{ContactChannel, ContactTier},
+    statuses,
+};
+use trusty_tail::migration::{Migrator, MigratorTrait};
+
+pub type BotApiCalls = mpsc::UnboundedReceiver<(String, serde_json::Value)>;
+
+pub fn free_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+// Answers the Bot API methods the bot calls and hands over every call as the
+// lowercase method name and the JSON body
+async fn fake_bot_api(
+    State(tx): State<mpsc::UnboundedSender<(String, serde_json::Value)>>,
+    uri: Uri,
+    body: String,
+) -> String {
+    let method = uri.path().rsplit('/').next().unwrap().to_lowercase();
+    let _ = tx.send((
+        method.clone(),
+        serde_json::from_str(&body).unwrap_or_default(),
+    ));
+    if method == "getme" {
+        r#"{"ok": true, "result": {
+            "id": 1,
+            "is_bot": true,
+            "first_name": "Trusty Tail",
+            "username": "trusty_tail_bot",
+            "can_join_groups": false,
+            "can_read_all_group_messages": false,
+            "supports_inline_queries": false
+        }}"#
+        .to_string()
+    } else if method.starts_with("send") {
+        r#"{"ok": true, "result": {
+            "message_id": 1,
+            "date": 1700000000,
+            "chat": {"id": 42, "type": "private", "first_name": "Test"},
+            "from": {"id": 1, "is_bot": true, "first_name": "Trusty Tail"},
+            "text": "ok"
+        }}"#
+        .to_string()
+    } else {
+        r#"{"ok": true, "result": true}"#.to_string()
+    }
+}
+
+// A bot talking to a local fake of the Bot API
+pub fn spawn_bot_api() -> (Bot, BotApiCalls) {
+    let address = free_address();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let app = Router::new().fallback(fake_bot_api).with_state(tx);
+    tokio::spawn(axum::Server::bind(&address).serve(app.into_make_service()));
+
+    let bot = Bot::new("123:test").set_api_url(format!("http://{}", address).parse().unwrap());
+    (bot, rx)
+}
+
+const TEST_KEYS: &str = "test:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+// Tests in a binary run in parallel, the ones sharing the database take turns
+static DATABASE_LOCK: Mutex<()> = Mutex::const_new(());
+
+pub struct TestDatabase {
+    pub connection: DatabaseConnection,
+    _lock: MutexGuard<'static, ()>,
+}
+
+// An empty, migrated database from `TEST_DB_URL`, which loses all its data.
+// Returns `None` when it isn't set, so the tests that need it are skipped.
+pub async fn get_test_database() -> Option<TestDatabase> {
+    let url = match env::var("TEST_DB_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("TEST_DB_URL isn't set, skipping");
+            return None;
+        }
+    };
+    let lock = DATABASE_LOCK.lock().await;
+
+    // The encryption migrations need keys, even with nothing to encrypt
+    env::set_var("ENCRYPTION_KEYS", TEST_KEYS);
+    env::set_var("ENCRYPTION_KEY_ID", "test");
+    let connection = Database::connect(url).await.unwrap();
+    Migrator::up(&connection, None).await.unwrap();
+    connection
+        .execute_unprepared(
+            "DO $$ DECLARE t text; BEGIN \
+             FOR t IN SELECT tablename FROM pg_tables \
+             WHERE schemaname = current_schema() AND tablename <> 'seaql_migrations' LOOP \
+             EXECUTE format('TRUNCATE %I RESTART IDENTITY CASCADE', t); \
+             END LOOP; END $$",
+        )
+        .await
+        .unwrap();
+
+    Some(TestDatabase {
+        connection,
+        _lock: lock,
+    })
+}
+
+pub fn get_test_config() -> Config {
+    Config {
+        db_url: String::new(),
+        sentry_url: String::new(),
+        transport: Transport::Polling,
+        reminder_after_hours: 6,
+        second_reminder_after_hours: 12,
+        heads_up_after_hours: 18,
+        alert_tier_wait_minutes: 60,
+        health_nudge_interval_hours: 72,
+        scheduler_enabled: false,
+        scheduler_interval_minutes: 10,
+        keyring: Keyring::parse("test", TEST_KEYS).unwrap(),
+        smtp: None,
+        sms_gateway: None,
+    }
+}
+
+pub fn get_tera() -> Tera {
+    Tera::new("templates/**/*").unwrap()
+}
+
+// An owner with monitoring on, who last checked in at `alive_at`
+pub async fn create_owner(connection: &DatabaseConnection, chat_id: i64, alive_at: NaiveDateTime) {
+    profiles::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id),
+        username: ActiveValue::Set(format!("owner{}", chat_id)),
+        time_zone: ActiveValue::Set("UTC".to_string()),
+        language: ActiveValue::Set(Default::default()),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap();
+    statuses::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id),
+        enabled: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap();
+    alive_events::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id),
+        timestamp: ActiveValue::Set(alive_at),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap();
+}
+
+pub async fn create_contact(
+    connection: &DatabaseConnection,
+    owner_chat_id: i64,
+    chat_id: i64,
+    tier: ContactTier,
+) -> secondary_owners::Model {
+    profiles::ActiveModel {
+        chat_id: ActiveValue::Set(chat_id),
+        username: ActiveValue::Set(format!("contact{}", chat_id)),
+        time_zone: ActiveValue::Set("UTC".to_string()),
+        language: ActiveValue::Set(Default::default()),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap();
+    secondary_owners::ActiveModel {
+        primary_owner_chat_id: ActiveValue::Set(owner_chat_id),
+        secondary_owner_chat_id: ActiveValue::Set(Some(chat_id)),
+        created_at: ActiveValue::Set(Utc::now().naive_utc()),
+        tier: ActiveValue::Set(tier),
+        channel: ActiveValue::Set(ContactChannel::Telegram),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap()
+}
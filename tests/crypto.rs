@@ -0,0 +1,102 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use trusty_tail::crypto::Keyring;
+
+fn get_key(byte: u8) -> String {
+    STANDARD.encode([byte; 32])
+}
+
+fn get_keyring(active_key_id: &str, keys: &[(&str, u8)]) -> Keyring {
+    let keys = keys
+        .iter()
+        .map(|(id, byte)| format!("{}:{}", id, get_key(*byte)))
+        .collect::<Vec<_>>()
+        .join(",");
+    Keyring::parse(active_key_id, &keys).unwrap()
+}
+
+#[test]
+fn sealed_text_opens_with_the_same_keyring() {
+    let keyring = get_keyring("a", &[("a", 1)]);
+    let sealed = keyring.seal("Key box code 1234").unwrap();
+
+    assert_eq!(sealed.key_id, "a");
+    assert!(!String::from_utf8_lossy(&sealed.ciphertext).contains("1234"));
+    assert_eq!(keyring.open(&sealed).unwrap(), "Key box code 1234");
+}
+
+#[test]
+fn every_seal_gets_its_own_data_key_and_nonce() {
+    let keyring = get_keyring("a", &[("a", 1)]);
+    let first = keyring.seal("same").unwrap();
+    let second = keyring.seal("same").unwrap();
+
+    assert_ne!(first.nonce, second.nonce);
+    assert_ne!(first.data_key, second.data_key);
+    assert_ne!(first.ciphertext, second.ciphertext);
+}
+
+#[test]
+fn open_fails_with_a_wrong_or_unknown_key() {
+    let sealed = get_keyring("a", &[("a", 1)]).seal("secret").unwrap();
+
+    // Same id, different key material
+    assert!(get_keyring("a", &[("a", 2)]).open(&sealed).is_err());
+    assert!(get_keyring("b", &[("b", 1)]).open(&sealed).is_err());
+}
+
+#[test]
+fn open_fails_on_tampering() {
+    let keyring = get_keyring("a", &[("a", 1)]);
+    let sealed = keyring.seal("secret").unwrap();
+
+    let mut tampered = sealed.clone();
+    tampered.ciphertext[0] ^= 1;
+    assert!(keyring.open(&tampered).is_err());
+
+    let mut tampered = sealed.clone();
+    tampered.nonce[0] ^= 1;
+    assert!(keyring.open(&tampered).is_err());
+
+    let mut tampered = sealed.clone();
+    tampered.nonce.pop();
+    assert!(keyring.open(&tampered).is_err());
+
+    let mut tampered = sealed.clone();
+    let last = tampered.data_key.len() - 1;
+    tampered.data_key[last] ^= 1;
+    assert!(keyring.open(&tampered).is_err());
+
+    let mut tampered = sealed;
+    tampered.data_key.truncate(12);
+    assert!(keyring.open(&tampered).is_err());
+}
+
+#[test]
+fn rewrap_moves_the_data_key_to_the_active_key() {
+    let sealed = get_keyring("old", &[("old", 1)]).seal("secret").unwrap();
+
+    let rotated = get_keyring("new", &[("old", 1), ("new", 2)])
+        .rewrap(&sealed)
+        .unwrap();
+    assert_eq!(rotated.key_id, "new");
+    assert_eq!(rotated.ciphertext, sealed.ciphertext);
+    assert_eq!(rotated.nonce, sealed.nonce);
+
+    // The old key can be dropped once everything is rewrapped
+    let keyring = get_keyring("new", &[("new", 2)]);
+    assert_eq!(keyring.open(&rotated).unwrap(), "secret");
+    assert!(keyring.open(&sealed).is_err());
+}
+
+#[test]
+fn parse_rejects_malformed_keys() {
+    let short_key = STANDARD.encode([1u8; 16]);
+
+    assert!(Keyring::parse("a", &format!("a:{}", get_key(1))).is_ok());
+    assert!(Keyring::parse("a", &format!(" a : {} , b:{} ", get_key(1), get_key(2))).is_ok());
+    assert!(Keyring::parse("a", "a:not base64!").is_err());
+    assert!(Keyring::parse("a", &format!("a:{}", short_key)).is_err());
+    assert!(Keyring::parse("a", &get_key(1)).is_err());
+    assert!(Keyring::parse("a", &format!("b:{}", get_key(1))).is_err());
+    assert!(Keyring::parse("a", "").is_err());
+}
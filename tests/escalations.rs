@@ -0,0 +1,48 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use trusty_tail::entity::settings;
+use trusty_tail::escalations::utils::get_next_alert_at;
+
+fn get_settings() -> settings::Model {
+    settings::Model {
+        id: 1,
+        chat_id: 1,
+        check_in_interval_hours: 24,
+        alert_grace_hours: 12,
+        check_in_window_start: None,
+        check_in_window_end: None,
+        quiet_hours_start: None,
+        quiet_hours_end: None,
+    }
+}
+
+fn at(hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 7, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        + Duration::hours(hour as i64)
+}
+
+#[test]
+fn next_alert_waits_for_the_next_prompt() {
+    let settings = get_settings();
+    // Answered the last prompt, the next one goes out a day after the check-in
+    let next_alert_at = get_next_alert_at(&settings, Some(at(10)), Some(at(9)), at(11));
+    assert_eq!(next_alert_at, at(10 + 24 + 12));
+    // Overdue, but the prompt is held back until now
+    let next_alert_at = get_next_alert_at(&settings, Some(at(0)), None, at(30));
+    assert_eq!(next_alert_at, at(30 + 12));
+    assert_eq!(get_next_alert_at(&settings, None, None, at(5)), at(5 + 12));
+}
+
+#[test]
+fn next_alert_counts_from_a_pending_prompt() {
+    let settings = get_settings();
+    let next_alert_at = get_next_alert_at(&settings, Some(at(0)), Some(at(24)), at(30));
+    assert_eq!(next_alert_at, at(24 + 12));
+    let next_alert_at = get_next_alert_at(&settings, None, Some(at(24)), at(30));
+    assert_eq!(next_alert_at, at(24 + 12));
+    // The grace period is over, the alert goes out on the next run
+    let next_alert_at = get_next_alert_at(&settings, Some(at(0)), Some(at(24)), at(40));
+    assert_eq!(next_alert_at, at(40));
+}
@@ -0,0 +1,46 @@
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection};
+use trusty_tail::entity::{secondary_owners::ContactTier, statuses};
+use trusty_tail::health::utils::select_monitored_profiles;
+
+mod common;
+
+use common::{create_contact, create_owner, get_test_database};
+
+async fn get_monitored_chat_ids(connection: &DatabaseConnection) -> Vec<i64> {
+    let mut chat_ids = select_monitored_profiles()
+        .all(connection)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.chat_id)
+        .collect::<Vec<_>>();
+    chat_ids.sort();
+    chat_ids
+}
+
+#[tokio::test]
+async fn contacts_without_a_setup_of_their_own_are_not_nudged() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let now = Utc::now().naive_utc();
+    // A fresh owner, and an owner with a contact who came in through `/start <code>`
+    create_owner(connection, 1, now).await;
+    create_owner(connection, 3, now - Duration::days(1)).await;
+    create_contact(connection, 3, 2, ContactTier::Primary).await;
+    statuses::ActiveModel {
+        chat_id: ActiveValue::Set(2),
+        enabled: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap();
+    assert_eq!(get_monitored_chat_ids(connection).await, vec![1, 3]);
+
+    // Setting up a contact of their own makes them an owner too
+    create_contact(connection, 2, 4, ContactTier::Primary).await;
+    assert_eq!(get_monitored_chat_ids(connection).await, vec![1, 2, 3]);
+}
@@ -0,0 +1,102 @@
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait};
+use teloxide::types::ChatId;
+use trusty_tail::entity::{incidents, secondary_owners::ContactTier};
+use trusty_tail::i18n::Text;
+use trusty_tail::incidents::utils::select_open_incidents_for_contact;
+use trusty_tail::modules::incidents::handle_acknowledge_incident;
+
+mod common;
+
+use common::{create_contact, create_owner, get_tera, get_test_database, spawn_bot_api};
+
+// Owner 1 with a contact in each tier, only the first tier was alerted
+async fn create_incident(connection: &DatabaseConnection) -> incidents::Model {
+    let now = Utc::now().naive_utc();
+    create_owner(connection, 1, now - Duration::days(3)).await;
+    create_contact(connection, 1, 2, ContactTier::Primary).await;
+    create_contact(connection, 1, 3, ContactTier::Secondary).await;
+    incidents::ActiveModel {
+        chat_id: ActiveValue::Set(1),
+        created_at: ActiveValue::Set(now),
+        notified_tier: ActiveValue::Set(Some(ContactTier::Primary)),
+        notified_at: ActiveValue::Set(Some(now)),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn open_incident_is_shown_only_to_alerted_tiers() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let incident = create_incident(connection).await;
+
+    let shown = select_open_incidents_for_contact(ChatId(2))
+        .all(connection)
+        .await
+        .unwrap();
+    assert_eq!(shown, vec![incident.clone()]);
+    let shown = select_open_incidents_for_contact(ChatId(3))
+        .all(connection)
+        .await
+        .unwrap();
+    assert!(shown.is_empty());
+
+    // The next tier sees it once it's alerted too
+    incidents::ActiveModel {
+        notified_tier: ActiveValue::Set(Some(ContactTier::Secondary)),
+        ..incident.into()
+    }
+    .update(connection)
+    .await
+    .unwrap();
+    let shown = select_open_incidents_for_contact(ChatId(3))
+        .all(connection)
+        .await
+        .unwrap();
+    assert_eq!(shown.len(), 1);
+}
+
+#[tokio::test]
+async fn contact_who_was_not_alerted_is_told_so() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let incident = create_incident(connection).await;
+    let (bot, mut calls) = spawn_bot_api();
+    let tera = get_tera();
+
+    let state = handle_acknowledge_incident(&bot, ChatId(3), incident.id, connection, &tera)
+        .await
+        .unwrap();
+    assert_eq!(state, None);
+    let (method, body) = calls.recv().await.unwrap();
+    assert_eq!(method, "sendmessage");
+    assert_eq!(body["chat_id"], 3);
+    assert_eq!(
+        body["text"],
+        Text::IncidentNotAlerted.get(Default::default())
+    );
+    let incident = incidents::Entity::find_by_id(incident.id)
+        .one(connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(incident.acknowledged_by, None);
+
+    handle_acknowledge_incident(&bot, ChatId(2), incident.id, connection, &tera)
+        .await
+        .unwrap();
+    let incident = incidents::Entity::find_by_id(incident.id)
+        .one(connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(incident.acknowledged_by, Some(2));
+}
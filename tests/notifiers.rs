@@ -0,0 +1,264 @@
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::{extract::State, routing::post, Router};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use trusty_tail::config::{SmsGatewayConfig, SmtpConfig};
+use trusty_tail::emergency_info::utils::Attachment;
+use trusty_tail::entity::emergency_attachments::AttachmentKind;
+use trusty_tail::notifiers::email::EmailNotifier;
+use trusty_tail::notifiers::sms::SmsNotifier;
+use trusty_tail::notifiers::telegram::TelegramNotifier;
+use trusty_tail::notifiers::{Notification, Notifier, NotifyError};
+
+mod common;
+
+use common::{free_address, spawn_bot_api};
+
+fn get_notification() -> Notification {
+    Notification {
+        subject: "Trusty Tail: news about @owner".to_string(),
+        html: "<strong>🚨 @owner</strong> needs help &amp; food".to_string(),
+        ..Default::default()
+    }
+}
+
+async fn receive<T>(rx: &mut mpsc::UnboundedReceiver<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("Stub didn't get the request")
+        .unwrap()
+}
+
+// Speaks just enough SMTP for one message and hands over the envelope and the data
+async fn smtp_stub(listener: tokio::net::TcpListener, tx: mpsc::UnboundedSender<String>) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+
+    let mut transcript = String::new();
+    let mut in_data = false;
+    while let Some(line) = lines.next_line().await.unwrap() {
+        transcript.push_str(&line);
+        transcript.push('\n');
+
+        let reply: &[u8] = if in_data {
+            if line != "." {
+                continue;
+            }
+            in_data = false;
+            b"250 Queued\r\n"
+        } else if line.starts_with("EHLO") {
+            b"250-localhost\r\n250 8BITMIME\r\n"
+        } else if line == "DATA" {
+            in_data = true;
+            b"354 Go ahead\r\n"
+        } else if line == "QUIT" {
+            writer.write_all(b"221 Bye\r\n").await.unwrap();
+            break;
+        } else {
+            b"250 OK\r\n"
+        };
+        writer.write_all(reply).await.unwrap();
+
+        if !in_data && line == "." {
+            tx.send(transcript.clone()).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn email_notifier_sends_plain_text() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(smtp_stub(listener, tx));
+
+    let notifier = EmailNotifier::new(&SmtpConfig {
+        url: format!("smtp://{}", address),
+        from: "Trusty Tail <bot@example.com>".to_string(),
+    })
+    .unwrap();
+    notifier
+        .notify("contact@example.com", &get_notification())
+        .await
+        .unwrap();
+
+    let transcript = receive(&mut rx).await;
+    assert!(transcript.contains("MAIL FROM:<bot@example.com>"));
+    assert!(transcript.contains("RCPT TO:<contact@example.com>"));
+    assert!(transcript.contains("Subject: Trusty Tail: news about @owner"));
+    assert!(transcript.contains("Content-Type: text/plain"));
+    assert!(!transcript.contains("<strong>"));
+}
+
+async fn sms_gateway_stub(
+    State(tx): State<mpsc::UnboundedSender<(Option<String>, serde_json::Value)>>,
+    headers: HeaderMap,
+    body: String,
+) -> &'static str {
+    let token = headers
+        .get("Authorization")
+        .map(|x| x.to_str().unwrap().to_string());
+    tx.send((token, serde_json::from_str(&body).unwrap()))
+        .unwrap();
+    "{}"
+}
+
+#[tokio::test]
+async fn sms_notifier_posts_to_gateway() {
+    let address = free_address();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route("/send", post(sms_gateway_stub))
+        .with_state(tx);
+    tokio::spawn(axum::Server::bind(&address).serve(app.into_make_service()));
+
+    let notifier = SmsNotifier::new(&SmsGatewayConfig {
+        url: format!("http://{}/send", address).parse().unwrap(),
+        token: Some("gateway-token".to_string()),
+    });
+    notifier
+        .notify("+447946095800", &get_notification())
+        .await
+        .unwrap();
+
+    let (token, body) = receive(&mut rx).await;
+    assert_eq!(token.as_deref(), Some("Bearer gateway-token"));
+    assert_eq!(body["to"], "+447946095800");
+    assert_eq!(body["text"], "🚨 @owner needs help & food");
+}
+
+async fn notify_gateway_failing_with(status: StatusCode) -> Result<(), NotifyError> {
+    let address = free_address();
+    let app = Router::new().fallback(move || async move { (status, "") });
+    tokio::spawn(axum::Server::bind(&address).serve(app.into_make_service()));
+
+    let notifier = SmsNotifier::new(&SmsGatewayConfig {
+        url: format!("http://{}/send", address).parse().unwrap(),
+        token: None,
+    });
+    notifier.notify("+447946095800", &get_notification()).await
+}
+
+#[tokio::test]
+async fn sms_notifier_tells_transient_errors_from_permanent() {
+    let result = notify_gateway_failing_with(StatusCode::BAD_GATEWAY).await;
+    assert!(matches!(result, Err(NotifyError::Transient(_))));
+
+    let result = notify_gateway_failing_with(StatusCode::TOO_MANY_REQUESTS).await;
+    assert!(matches!(result, Err(NotifyError::Transient(_))));
+
+    let result = notify_gateway_failing_with(StatusCode::BAD_REQUEST).await;
+    assert!(matches!(result, Err(NotifyError::Permanent(_))));
+}
+
+#[tokio::test]
+async fn telegram_notifier_sends_html_with_keyboard() {
+    let (bot, mut rx) = spawn_bot_api();
+    let notifier = TelegramNotifier::new(bot);
+    let notification = Notification {
+        keyboard: Some(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("🙋", "/acknowledge_incident 1"),
+        ]])),
+        photos: vec![("photo-file-id".to_string(), "🐾 Rex".to_string())],
+        ..get_notification()
+    };
+    notifier.notify("42", &notification).await.unwrap();
+
+    let (method, body) = receive(&mut rx).await;
+    assert_eq!(method, "sendmessage");
+    assert_eq!(body["chat_id"], 42);
+    assert_eq!(body["parse_mode"], "HTML");
+    assert_eq!(body["text"], notification.html);
+    assert_eq!(
+        body["reply_markup"]["inline_keyboard"][0][0]["callback_data"],
+        "/acknowledge_incident 1"
+    );
+
+    let (method, _) = receive(&mut rx).await;
+    assert_eq!(method, "sendphoto");
+
+    let result = notifier.notify("not-a-chat", &notification).await;
+    assert!(matches!(result, Err(NotifyError::Permanent(_))));
+}
+
+// Answers every call the way Telegram does when the user blocked the bot
+async fn blocked_bot_api() -> (StatusCode, &'static str) {
+    (
+        StatusCode::FORBIDDEN,
+        r#"{"ok": false, "error_code": 403, "description": "Forbidden: bot was blocked by the user"}"#,
+    )
+}
+
+#[tokio::test]
+async fn telegram_notifier_gives_up_on_blocked_chats() {
+    let address = free_address();
+    let app = Router::new().fallback(blocked_bot_api);
+    tokio::spawn(axum::Server::bind(&address).serve(app.into_make_service()));
+
+    let bot = Bot::new("123:test").set_api_url(format!("http://{}", address).parse().unwrap());
+    let result = TelegramNotifier::new(bot)
+        .notify("42", &get_notification())
+        .await;
+    assert!(matches!(result, Err(NotifyError::Unreachable(_))));
+}
+
+#[tokio::test]
+async fn email_notifier_retries_when_server_is_down() {
+    let notifier = EmailNotifier::new(&SmtpConfig {
+        url: format!("smtp://{}", free_address()),
+        from: "bot@example.com".to_string(),
+    })
+    .unwrap();
+    let result = notifier
+        .notify("contact@example.com", &get_notification())
+        .await;
+    assert!(matches!(result, Err(NotifyError::Transient(_))));
+}
+
+// Sends text messages and fails everything else
+async fn flaky_media_bot_api(uri: Uri) -> (StatusCode, &'static str) {
+    if uri.path().to_lowercase().ends_with("/sendmessage") {
+        (
+            StatusCode::OK,
+            r#"{"ok": true, "result": {
+                "message_id": 1,
+                "date": 1700000000,
+                "chat": {"id": 42, "type": "private", "first_name": "Test"},
+                "text": "ok"
+            }}"#,
+        )
+    } else {
+        (
+            StatusCode::BAD_REQUEST,
+            r#"{"ok": false, "error_code": 400, "description": "Bad Request: wrong file identifier"}"#,
+        )
+    }
+}
+
+#[tokio::test]
+async fn telegram_notifier_succeeds_once_the_text_is_sent() {
+    let address = free_address();
+    let app = Router::new().fallback(flaky_media_bot_api);
+    tokio::spawn(axum::Server::bind(&address).serve(app.into_make_service()));
+
+    let bot = Bot::new("123:test").set_api_url(format!("http://{}", address).parse().unwrap());
+    let notification = Notification {
+        attachments: vec![Attachment {
+            id: 1,
+            kind: AttachmentKind::Document,
+            file_id: Some("document-file-id".to_string()),
+            caption: None,
+            latitude: None,
+            longitude: None,
+        }],
+        photos: vec![("photo-file-id".to_string(), "🐾 Rex".to_string())],
+        ..get_notification()
+    };
+    let result = TelegramNotifier::new(bot).notify("42", &notification).await;
+    assert!(result.is_ok());
+}
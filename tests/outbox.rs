@@ -0,0 +1,45 @@
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
+use tokio::time::Instant;
+use trusty_tail::jobs::dispatch_outbox::{RateLimiter, CHAT_INTERVAL, GLOBAL_INTERVAL};
+use trusty_tail::outbox::utils::OutboxPayload;
+
+#[tokio::test]
+async fn rate_limiter_spaces_out_messages() {
+    let mut limiter = RateLimiter::default();
+    let started_at = Instant::now();
+    for chat_id in 1..=4 {
+        assert!(limiter.is_ready(chat_id, Instant::now()));
+        limiter.acquire(Some(chat_id)).await;
+    }
+    assert!(started_at.elapsed() >= GLOBAL_INTERVAL * 3);
+
+    let now = Instant::now();
+    assert!(!limiter.is_ready(1, now));
+    assert!(limiter.is_ready(5, now));
+    assert!(limiter.is_ready(1, now + CHAT_INTERVAL));
+}
+
+// Queued messages outlive deploys, the stored format has to stay readable
+#[test]
+fn payload_is_stored_as_tagged_json() {
+    let payload = OutboxPayload::Message {
+        text: "<b>Hi</b>".to_string(),
+        parse_mode: Some(ParseMode::Html),
+        keyboard: Some(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("✅", "/mark_alive"),
+        ]])),
+    };
+    let value = serde_json::to_value(&payload).unwrap();
+    assert_eq!(value["type"], "message");
+    assert_eq!(value["parse_mode"], "HTML");
+    assert_eq!(
+        serde_json::from_value::<OutboxPayload>(value).unwrap(),
+        payload
+    );
+
+    let value = serde_json::json!({"type": "delete_message", "message_id": 7});
+    assert_eq!(
+        serde_json::from_value::<OutboxPayload>(value).unwrap(),
+        OutboxPayload::DeleteMessage { message_id: 7 }
+    );
+}
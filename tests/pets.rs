@@ -0,0 +1,112 @@
+use sea_orm::EntityTrait;
+use teloxide::types::ChatId;
+use trusty_tail::crypto::Keyring;
+use trusty_tail::emergency_info::utils::rotate_keys;
+use trusty_tail::entity::pets;
+use trusty_tail::pets::utils::{add_pet, get_pet, set_pet_field, PetCare};
+use trusty_tail::types::PetField;
+
+mod common;
+
+use common::{get_test_config, get_test_database};
+
+#[tokio::test]
+async fn pet_care_notes_are_sealed() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let keyring = get_test_config().keyring;
+    let chat_id = ChatId(42);
+
+    let pet = add_pet(connection, chat_id, "Rex".to_string())
+        .await
+        .unwrap();
+    for (field, value) in [
+        (PetField::MedicalNotes, "Insulin twice a day"),
+        (PetField::Diet, "Grain free"),
+        (PetField::VetContact, "+123456"),
+    ] {
+        set_pet_field(
+            connection,
+            &keyring,
+            chat_id,
+            pet.id,
+            field,
+            Some(value.to_string()),
+        )
+        .await
+        .unwrap();
+    }
+    set_pet_field(connection, &keyring, chat_id, pet.id, PetField::Diet, None)
+        .await
+        .unwrap();
+
+    let row = pets::Entity::find_by_id(pet.id)
+        .one(connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let ciphertext = row.care_ciphertext.unwrap();
+    assert!(!String::from_utf8_lossy(&ciphertext).contains("Insulin"));
+
+    let pet = get_pet(connection, &keyring, chat_id, pet.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        pet.care,
+        PetCare {
+            medical_notes: Some("Insulin twice a day".to_string()),
+            diet: None,
+            vet_contact: Some("+123456".to_string()),
+        }
+    );
+}
+
+#[tokio::test]
+async fn pet_care_notes_are_rotated() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let keyring = get_test_config().keyring;
+    let chat_id = ChatId(42);
+
+    let pet = add_pet(connection, chat_id, "Rex".to_string())
+        .await
+        .unwrap();
+    set_pet_field(
+        connection,
+        &keyring,
+        chat_id,
+        pet.id,
+        PetField::MedicalNotes,
+        Some("Insulin twice a day".to_string()),
+    )
+    .await
+    .unwrap();
+
+    let keyring = Keyring::parse(
+        "next",
+        "test:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=,\
+         next:AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=",
+    )
+    .unwrap();
+    assert_eq!(rotate_keys(connection, &keyring).await.unwrap(), 1);
+
+    let row = pets::Entity::find_by_id(pet.id)
+        .one(connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.care_key_id.as_deref(), Some("next"));
+    let pet = get_pet(connection, &keyring, chat_id, pet.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        pet.care.medical_notes.as_deref(),
+        Some("Insulin twice a day")
+    );
+}
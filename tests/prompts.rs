@@ -0,0 +1,87 @@
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveValue, ColumnTrait, EntityTrait, QueryFilter};
+use trusty_tail::entity::{
+    escalations, outbox_messages, prompt_events, secondary_owners::ContactTier,
+};
+use trusty_tail::jobs::{confirm_alive, dispatch_outbox, send_alerts};
+use trusty_tail::notifiers::Notifiers;
+
+mod common;
+
+use common::{
+    create_contact, create_owner, get_tera, get_test_config, get_test_database, spawn_bot_api,
+};
+
+#[tokio::test]
+async fn queued_prompt_does_not_start_the_escalation() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let connection = &database.connection;
+    let (bot, mut calls) = spawn_bot_api();
+    let config = get_test_config();
+    let notifiers = Notifiers::new(bot.clone(), &config).unwrap();
+    let tera = get_tera();
+    let now = Utc::now().naive_utc();
+    create_owner(connection, 1, now - Duration::days(3)).await;
+    create_contact(connection, 1, 2, ContactTier::Primary).await;
+
+    // A second run doesn't queue the prompt again
+    confirm_alive::run(connection).await.unwrap();
+    confirm_alive::run(connection).await.unwrap();
+    let queued = outbox_messages::Entity::find()
+        .all(connection)
+        .await
+        .unwrap();
+    assert_eq!(queued.len(), 1);
+    assert!(queued[0].check_in_prompt);
+
+    send_alerts::run(connection, &notifiers, &config, &tera)
+        .await
+        .unwrap();
+    assert!(prompt_events::Entity::find()
+        .one(connection)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(escalations::Entity::find()
+        .one(connection)
+        .await
+        .unwrap()
+        .is_none());
+
+    let mut limiter = dispatch_outbox::RateLimiter::default();
+    let sent = dispatch_outbox::run(connection, &bot, &notifiers, &mut limiter)
+        .await
+        .unwrap();
+    assert_eq!(sent, 1);
+    let (method, body) = calls.recv().await.unwrap();
+    assert_eq!(method, "sendmessage");
+    assert_eq!(body["chat_id"], 1);
+    let prompted_at = prompt_events::Entity::find()
+        .filter(prompt_events::Column::ChatId.eq(1))
+        .one(connection)
+        .await
+        .unwrap()
+        .unwrap()
+        .timestamp;
+    assert!(prompted_at >= now);
+
+    // Once the delivered prompt goes unanswered, the ladder starts as usual
+    prompt_events::Entity::update_many()
+        .set(prompt_events::ActiveModel {
+            timestamp: ActiveValue::Set(now - Duration::hours(7)),
+            ..Default::default()
+        })
+        .exec(connection)
+        .await
+        .unwrap();
+    send_alerts::run(connection, &notifiers, &config, &tera)
+        .await
+        .unwrap();
+    assert!(escalations::Entity::find()
+        .one(connection)
+        .await
+        .unwrap()
+        .is_some());
+}
@@ -0,0 +1,57 @@
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait};
+use teloxide::dispatching::dialogue::Storage;
+use teloxide::types::ChatId;
+use trusty_tail::entity::dialogues;
+use trusty_tail::storage::DatabaseStorage;
+use trusty_tail::types::BotDialogState;
+
+mod common;
+
+use common::get_test_database;
+
+// E.g. a state saved by an older version of the bot
+#[tokio::test]
+async fn unreadable_dialogue_is_reset() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    dialogues::ActiveModel {
+        chat_id: ActiveValue::Set(42),
+        state: ActiveValue::Set(r#""WaitingForSomethingRemoved""#.to_string()),
+        ..Default::default()
+    }
+    .insert(&database.connection)
+    .await
+    .unwrap();
+
+    let storage = DatabaseStorage::new(database.connection.clone());
+    let dialogue = Storage::<BotDialogState>::get_dialogue(storage, ChatId(42))
+        .await
+        .unwrap();
+    assert_eq!(dialogue, None);
+    let rows = dialogues::Entity::find()
+        .all(&database.connection)
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+async fn dialogue_survives_a_round_trip() {
+    let Some(database) = get_test_database().await else {
+        return;
+    };
+    let storage = DatabaseStorage::new(database.connection.clone());
+    Storage::<BotDialogState>::update_dialogue(
+        storage.clone(),
+        ChatId(42),
+        BotDialogState::WaitingForInvite,
+    )
+    .await
+    .unwrap();
+
+    let dialogue = Storage::<BotDialogState>::get_dialogue(storage, ChatId(42))
+        .await
+        .unwrap();
+    assert_eq!(dialogue, Some(BotDialogState::WaitingForInvite));
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+use teloxide::prelude::*;
+use tokio::sync::mpsc;
+use trusty_tail::config::{Transport, WebhookConfig};
+use trusty_tail::transport;
+
+mod common;
+
+use common::{free_address, spawn_bot_api};
+
+const SECRET: &str = "test-secret";
+
+const UPDATE: &str = r#"{
+    "update_id": 1,
+    "message": {
+        "message_id": 1,
+        "date": 1700000000,
+        "chat": {"id": 42, "type": "private", "first_name": "Test"},
+        "from": {"id": 42, "is_bot": false, "first_name": "Test"},
+        "text": "hello"
+    }
+}"#;
+
+async fn post_update(client: &reqwest::Client, url: &str, secret: &str) -> reqwest::StatusCode {
+    for _ in 0..50 {
+        let response = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Telegram-Bot-Api-Secret-Token", secret)
+            .body(UPDATE)
+            .send()
+            .await;
+        match response {
+            Ok(response) => return response.status(),
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+    panic!("Webhook listener didn't start");
+}
+
+#[tokio::test]
+async fn webhook_update_reaches_handler() {
+    let (bot, _calls) = spawn_bot_api();
+
+    let webhook_address = free_address();
+    let webhook_url = format!("http://{}/webhook", webhook_address);
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let handler = Update::filter_message().endpoint(
+        |message: Message, tx: mpsc::UnboundedSender<String>| async move {
+            tx.send(message.text().unwrap_or_default().to_string())
+                .unwrap();
+            respond(())
+        },
+    );
+    let dispatcher = Dispatcher::builder(bot.clone(), handler)
+        .dependencies(dptree::deps![tx])
+        .build();
+    let config = WebhookConfig {
+        address: webhook_address,
+        url: webhook_url.parse().unwrap(),
+        secret: SECRET.to_string(),
+    };
+    tokio::spawn(transport::dispatch(
+        dispatcher,
+        bot,
+        Transport::Webhook(config),
+    ));
+
+    let client = reqwest::Client::new();
+
+    let status = post_update(&client, &webhook_url, "wrong-secret").await;
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+
+    let status = post_update(&client, &webhook_url, SECRET).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+
+    let text = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("Handler didn't run")
+        .unwrap();
+    assert_eq!(text, "hello");
+    assert!(
+        rx.try_recv().is_err(),
+        "Rejected update reached the handler"
+    );
+}